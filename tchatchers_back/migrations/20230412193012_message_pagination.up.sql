@@ -0,0 +1,2 @@
+-- Add up migration script here
+CREATE INDEX IF NOT EXISTS idx_message_room_timestamp ON MESSAGE(room, timestamp DESC, uuid DESC);
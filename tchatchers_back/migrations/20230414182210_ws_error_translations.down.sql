@@ -0,0 +1,8 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'ws_error_message_not_persisted',
+'ws_error_messages_not_visible'));
+
+DELETE FROM LABEL WHERE name IN (
+'ws_error_message_not_persisted',
+'ws_error_messages_not_visible');
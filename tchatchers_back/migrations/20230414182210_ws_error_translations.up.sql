@@ -0,0 +1,10 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('ws_error_message_not_persisted', 'Your message couldn''t be sent, please try again later.'),
+('ws_error_messages_not_visible', 'You can''t mark messages from another room as seen.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='ws_error_message_not_persisted'), 2, 'Votre message n’a pas pu être envoyé, veuillez réessayer plus tard.'),
+((SELECT id FROM label WHERE name='ws_error_messages_not_visible'), 2, 'Vous ne pouvez pas marquer comme lus des messages d’un autre salon.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,16 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'message_edited',
+'message_previous_versions',
+'edit_message',
+'ws_error_message_not_editable'));
+
+DELETE FROM LABEL WHERE name IN (
+'message_edited',
+'message_previous_versions',
+'edit_message',
+'ws_error_message_not_editable');
+
+DROP TABLE MESSAGE_REVISION;
+
+ALTER TABLE MESSAGE DROP COLUMN edited_at;
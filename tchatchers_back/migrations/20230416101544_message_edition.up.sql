@@ -0,0 +1,29 @@
+-- Add up migration script here
+ALTER TABLE MESSAGE ADD COLUMN edited_at TIMESTAMPTZ;
+
+CREATE TABLE MESSAGE_REVISION (
+    id SERIAL PRIMARY KEY,
+    message_uuid UUID NOT NULL,
+    content VARCHAR NOT NULL,
+    revised_at TIMESTAMPTZ NOT NULL,
+    CONSTRAINT fk_message
+        FOREIGN KEY(message_uuid)
+        REFERENCES MESSAGE(uuid)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_message_revision_message ON MESSAGE_REVISION(message_uuid);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('message_edited', '(edited)'),
+('message_previous_versions', 'Previous versions'),
+('edit_message', 'Edit'),
+('ws_error_message_not_editable', 'You can only edit your own messages.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='message_edited'), 2, '(modifié)'),
+((SELECT id FROM label WHERE name='message_previous_versions'), 2, 'Versions précédentes'),
+((SELECT id FROM label WHERE name='edit_message'), 2, 'Modifier'),
+((SELECT id FROM label WHERE name='ws_error_message_not_editable'), 2, 'Vous ne pouvez modifier que vos propres messages.')
+ON CONFLICT DO NOTHING;
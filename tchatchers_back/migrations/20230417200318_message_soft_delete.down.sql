@@ -0,0 +1,14 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'message_removed',
+'delete_message',
+'ws_error_messages_not_deletable'));
+
+DELETE FROM LABEL WHERE name IN (
+'message_removed',
+'delete_message',
+'ws_error_messages_not_deletable');
+
+ALTER TABLE MESSAGE DROP CONSTRAINT fk_deleted_by;
+ALTER TABLE MESSAGE DROP COLUMN deleted_by;
+ALTER TABLE MESSAGE DROP COLUMN deleted_at;
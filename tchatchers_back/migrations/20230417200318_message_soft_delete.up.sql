@@ -0,0 +1,19 @@
+-- Add up migration script here
+ALTER TABLE MESSAGE ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE MESSAGE ADD COLUMN deleted_by INTEGER;
+ALTER TABLE MESSAGE ADD CONSTRAINT fk_deleted_by
+    FOREIGN KEY(deleted_by)
+    REFERENCES CHATTER(id)
+    ON DELETE SET NULL;
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('message_removed', 'This message has been removed'),
+('delete_message', 'Delete'),
+('ws_error_messages_not_deletable', 'Some of the messages couldn''t be deleted.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='message_removed'), 2, 'Ce message a été supprimé'),
+((SELECT id FROM label WHERE name='delete_message'), 2, 'Supprimer'),
+((SELECT id FROM label WHERE name='ws_error_messages_not_deletable'), 2, 'Certains messages n’ont pas pu être supprimés.')
+ON CONFLICT DO NOTHING;
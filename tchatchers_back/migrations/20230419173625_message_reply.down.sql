@@ -0,0 +1,15 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'reply_message',
+'replying_to',
+'cancel_reply',
+'ws_error_reply_parent_not_found'));
+
+DELETE FROM LABEL WHERE name IN (
+'reply_message',
+'replying_to',
+'cancel_reply',
+'ws_error_reply_parent_not_found');
+
+ALTER TABLE MESSAGE DROP CONSTRAINT fk_reply_to;
+ALTER TABLE MESSAGE DROP COLUMN reply_to;
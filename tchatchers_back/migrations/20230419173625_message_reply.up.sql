@@ -0,0 +1,20 @@
+-- Add up migration script here
+ALTER TABLE MESSAGE ADD COLUMN reply_to UUID;
+ALTER TABLE MESSAGE ADD CONSTRAINT fk_reply_to
+    FOREIGN KEY(reply_to)
+    REFERENCES MESSAGE(uuid)
+    ON DELETE SET NULL;
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('reply_message', 'Reply'),
+('replying_to', 'Replying to'),
+('cancel_reply', 'Cancel'),
+('ws_error_reply_parent_not_found', 'The message you replied to can''t be found in this room.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='reply_message'), 2, 'Répondre'),
+((SELECT id FROM label WHERE name='replying_to'), 2, 'En réponse à'),
+((SELECT id FROM label WHERE name='cancel_reply'), 2, 'Annuler'),
+((SELECT id FROM label WHERE name='ws_error_reply_parent_not_found'), 2, 'Le message auquel vous répondez est introuvable dans ce salon.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,10 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'add_reaction',
+'ws_error_invalid_reaction'));
+
+DELETE FROM LABEL WHERE name IN (
+'add_reaction',
+'ws_error_invalid_reaction');
+
+DROP TABLE MESSAGE_REACTION;
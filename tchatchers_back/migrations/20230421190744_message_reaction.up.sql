@@ -0,0 +1,26 @@
+-- Add up migration script here
+CREATE TABLE MESSAGE_REACTION (
+    message_uuid UUID NOT NULL,
+    user_id INTEGER NOT NULL,
+    emoji VARCHAR(32) NOT NULL,
+    reacted_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(message_uuid, user_id, emoji),
+    CONSTRAINT fk_message
+        FOREIGN KEY(message_uuid)
+        REFERENCES MESSAGE(uuid)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(user_id)
+        REFERENCES CHATTER(id)
+        ON DELETE CASCADE
+);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('add_reaction', 'React'),
+('ws_error_invalid_reaction', 'Only emojis can be used to react to a message.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='add_reaction'), 2, 'Réagir'),
+((SELECT id FROM label WHERE name='ws_error_invalid_reaction'), 2, 'Seuls les emojis peuvent être utilisés pour réagir à un message.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,12 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'is_typing',
+'are_typing',
+'and',
+'several_typing'));
+
+DELETE FROM LABEL WHERE name IN (
+'is_typing',
+'are_typing',
+'and',
+'several_typing');
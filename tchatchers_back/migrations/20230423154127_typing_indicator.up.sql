@@ -0,0 +1,14 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('is_typing', 'is typing…'),
+('are_typing', 'are typing…'),
+('and', 'and'),
+('several_typing', 'Several people are typing…')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='is_typing'), 2, 'est en train d''écrire…'),
+((SELECT id FROM label WHERE name='are_typing'), 2, 'sont en train d''écrire…'),
+((SELECT id FROM label WHERE name='and'), 2, 'et'),
+((SELECT id FROM label WHERE name='several_typing'), 2, 'Plusieurs personnes sont en train d''écrire…')
+ON CONFLICT DO NOTHING;
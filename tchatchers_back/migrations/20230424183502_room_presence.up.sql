@@ -0,0 +1,8 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('online_members', 'Online')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='online_members'), 2, 'En ligne')
+ON CONFLICT DO NOTHING;
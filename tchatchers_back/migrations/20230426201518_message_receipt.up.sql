@@ -0,0 +1,24 @@
+-- Add up migration script here
+CREATE TABLE MESSAGE_RECEIPT (
+    message_uuid UUID NOT NULL,
+    user_id INTEGER NOT NULL,
+    delivered_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    seen_at TIMESTAMPTZ,
+    PRIMARY KEY(message_uuid, user_id),
+    CONSTRAINT fk_message
+        FOREIGN KEY(message_uuid)
+        REFERENCES MESSAGE(uuid)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(user_id)
+        REFERENCES CHATTER(id)
+        ON DELETE CASCADE
+);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('seen_by', 'Seen by')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='seen_by'), 2, 'Vu par')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,14 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'search_messages',
+'no_search_result'));
+
+DELETE FROM LABEL WHERE name IN (
+'search_messages',
+'no_search_result');
+
+DROP INDEX idx_message_search;
+DROP TRIGGER message_search_config ON MESSAGE;
+DROP FUNCTION set_message_search_config;
+ALTER TABLE MESSAGE DROP COLUMN search_config;
+ALTER TABLE LANGUAGE DROP COLUMN search_config;
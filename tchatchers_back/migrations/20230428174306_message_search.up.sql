@@ -0,0 +1,40 @@
+-- Add up migration script here
+ALTER TABLE LANGUAGE ADD COLUMN search_config REGCONFIG NOT NULL DEFAULT 'simple';
+UPDATE LANGUAGE SET search_config = 'english' WHERE short = 'en';
+UPDATE LANGUAGE SET search_config = 'french' WHERE short = 'fr';
+
+ALTER TABLE MESSAGE ADD COLUMN search_config REGCONFIG NOT NULL DEFAULT 'simple';
+UPDATE MESSAGE m SET search_config = lang.search_config
+FROM CHATTER c
+INNER JOIN LOCALE l ON c.locale_id = l.id
+INNER JOIN LANGUAGE lang ON l.language_id = lang.id
+WHERE m.author = c.id;
+
+-- The language used to index a message is the one of its author.
+CREATE FUNCTION set_message_search_config() RETURNS TRIGGER AS $$
+BEGIN
+    SELECT lang.search_config INTO NEW.search_config
+    FROM CHATTER c
+    INNER JOIN LOCALE l ON c.locale_id = l.id
+    INNER JOIN LANGUAGE lang ON l.language_id = lang.id
+    WHERE c.id = NEW.author;
+    NEW.search_config := COALESCE(NEW.search_config, 'simple');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER message_search_config
+BEFORE INSERT ON MESSAGE
+FOR EACH ROW EXECUTE FUNCTION set_message_search_config();
+
+CREATE INDEX idx_message_search ON MESSAGE USING GIN (to_tsvector(search_config, content));
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('search_messages', 'Search messages'),
+('no_search_result', 'No message found')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='search_messages'), 2, 'Rechercher des messages'),
+((SELECT id FROM label WHERE name='no_search_result'), 2, 'Aucun message trouvé')
+ON CONFLICT DO NOTHING;
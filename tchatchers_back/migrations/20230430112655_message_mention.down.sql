@@ -0,0 +1,8 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'mentioned_you_in'));
+
+DELETE FROM LABEL WHERE name IN (
+'mentioned_you_in');
+
+DROP TABLE MENTION;
@@ -0,0 +1,22 @@
+-- Add up migration script here
+CREATE TABLE MENTION (
+    message_uuid UUID NOT NULL,
+    user_id INTEGER NOT NULL,
+    PRIMARY KEY(message_uuid, user_id),
+    CONSTRAINT fk_message
+        FOREIGN KEY(message_uuid)
+        REFERENCES MESSAGE(uuid)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(user_id)
+        REFERENCES CHATTER(id)
+        ON DELETE CASCADE
+);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('mentioned_you_in', 'mentioned you in')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='mentioned_you_in'), 2, 'vous a mentionné dans')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'direct_messages'));
+
+DELETE FROM LABEL WHERE name IN (
+'direct_messages');
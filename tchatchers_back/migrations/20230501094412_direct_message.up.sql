@@ -0,0 +1,8 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('direct_messages', 'Direct messages')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='direct_messages'), 2, 'Messages privés')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'attach_file', 'attachment_too_large', 'too_many_attachments'));
+
+DELETE FROM LABEL WHERE name IN (
+'attach_file', 'attachment_too_large', 'too_many_attachments');
+
+DROP TABLE ATTACHMENT;
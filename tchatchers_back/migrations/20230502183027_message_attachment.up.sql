@@ -0,0 +1,33 @@
+-- Add up migration script here
+CREATE TABLE ATTACHMENT (
+    id UUID PRIMARY KEY,
+    message_uuid UUID,
+    uploaded_by INTEGER NOT NULL,
+    file_name VARCHAR(255) NOT NULL,
+    mime_type VARCHAR(127) NOT NULL,
+    size BIGINT NOT NULL,
+    checksum CHAR(64) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_message
+        FOREIGN KEY(message_uuid)
+        REFERENCES MESSAGE(uuid)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(uploaded_by)
+        REFERENCES CHATTER(id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX attachment_message_uuid_idx ON ATTACHMENT(message_uuid);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('attach_file', 'Attach a file'),
+('attachment_too_large', 'The file is too large.'),
+('too_many_attachments', 'Too many files are attached to the message.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='attach_file'), 2, 'Joindre un fichier'),
+((SELECT id FROM label WHERE name='attachment_too_large'), 2, 'Le fichier est trop volumineux.'),
+((SELECT id FROM label WHERE name='too_many_attachments'), 2, 'Trop de fichiers sont joints au message.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,10 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'pinned_messages', 'pinned_by', 'pin_message', 'unpin_message',
+'ws_error_pin_not_allowed', 'ws_error_pinned_message_not_found'));
+
+DELETE FROM LABEL WHERE name IN (
+'pinned_messages', 'pinned_by', 'pin_message', 'unpin_message',
+'ws_error_pin_not_allowed', 'ws_error_pinned_message_not_found');
+
+DROP TABLE ROOM_PIN;
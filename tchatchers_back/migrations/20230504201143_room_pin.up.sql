@@ -0,0 +1,34 @@
+-- Add up migration script here
+CREATE TABLE ROOM_PIN (
+    room VARCHAR NOT NULL,
+    message_uuid UUID NOT NULL,
+    pinned_by INTEGER NOT NULL,
+    pinned_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(room, message_uuid),
+    CONSTRAINT fk_message
+        FOREIGN KEY(message_uuid)
+        REFERENCES MESSAGE(uuid)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(pinned_by)
+        REFERENCES CHATTER(id)
+        ON DELETE CASCADE
+);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('pinned_messages', 'Pinned messages'),
+('pinned_by', 'Pinned by'),
+('pin_message', 'Pin'),
+('unpin_message', 'Unpin'),
+('ws_error_pin_not_allowed', 'You aren''t allowed to pin messages in this room.'),
+('ws_error_pinned_message_not_found', 'The message to pin can''t be found in this room.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='pinned_messages'), 2, 'Messages épinglés'),
+((SELECT id FROM label WHERE name='pinned_by'), 2, 'Épinglé par'),
+((SELECT id FROM label WHERE name='pin_message'), 2, 'Épingler'),
+((SELECT id FROM label WHERE name='unpin_message'), 2, 'Désépingler'),
+((SELECT id FROM label WHERE name='ws_error_pin_not_allowed'), 2, 'Vous n’êtes pas autorisé à épingler des messages dans ce salon.'),
+((SELECT id FROM label WHERE name='ws_error_pinned_message_not_found'), 2, 'Le message à épingler est introuvable dans ce salon.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+CREATE TABLE ROOM_RETENTION (
+    room VARCHAR PRIMARY KEY,
+    max_age_days INTEGER CHECK (max_age_days > 0),
+    max_messages INTEGER CHECK (max_messages > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,8 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('slow_down', 'You''re sending messages too fast, please wait a moment.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='slow_down'), 2, 'Vous envoyez des messages trop rapidement, veuillez patienter un instant.')
+ON CONFLICT DO NOTHING;
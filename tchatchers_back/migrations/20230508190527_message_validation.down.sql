@@ -0,0 +1,6 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN (
+'ws_error_message_length', 'ws_error_message_forbidden_chars', 'ws_error_invalid_message'));
+
+DELETE FROM LABEL WHERE name IN (
+'ws_error_message_length', 'ws_error_message_forbidden_chars', 'ws_error_invalid_message');
@@ -0,0 +1,12 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('ws_error_message_length', 'A message must contain between {min} and {max} characters.'),
+('ws_error_message_forbidden_chars', 'Your message contains forbidden characters.'),
+('ws_error_invalid_message', 'Your message is invalid.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='ws_error_message_length'), 2, 'Un message doit contenir entre {min} et {max} caractères.'),
+((SELECT id FROM label WHERE name='ws_error_message_forbidden_chars'), 2, 'Votre message contient des caractères interdits.'),
+((SELECT id FROM label WHERE name='ws_error_invalid_message'), 2, 'Votre message est invalide.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name='ws_error_message_rejected');
+
+DELETE FROM LABEL WHERE name='ws_error_message_rejected';
+
+DROP TABLE MESSAGE_FLAG;
+
+DROP TABLE FILTER_RULE;
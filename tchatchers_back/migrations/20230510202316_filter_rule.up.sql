@@ -0,0 +1,36 @@
+-- Add up migration script here
+CREATE TABLE FILTER_RULE (
+    id SERIAL PRIMARY KEY,
+    pattern VARCHAR NOT NULL,
+    match_mode INTEGER NOT NULL,
+    action INTEGER NOT NULL,
+    created_by INTEGER,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(created_by)
+        REFERENCES CHATTER(id)
+        ON DELETE SET NULL
+);
+
+CREATE TABLE MESSAGE_FLAG (
+    message_uuid UUID NOT NULL,
+    rule_id INTEGER NOT NULL,
+    flagged_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(message_uuid, rule_id),
+    CONSTRAINT fk_message
+        FOREIGN KEY(message_uuid)
+        REFERENCES MESSAGE(uuid)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_filter_rule
+        FOREIGN KEY(rule_id)
+        REFERENCES FILTER_RULE(id)
+        ON DELETE CASCADE
+);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('ws_error_message_rejected', 'Your message contains words that aren''t allowed.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='ws_error_message_rejected'), 2, 'Votre message contient des mots qui ne sont pas autorisés.')
+ON CONFLICT DO NOTHING;
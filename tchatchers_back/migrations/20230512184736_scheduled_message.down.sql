@@ -0,0 +1,6 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN ('send_later', 'pick_send_date', 'message_scheduled', 'scheduled_messages', 'cancel_scheduled', 'send_at_out_of_range'));
+
+DELETE FROM LABEL WHERE name IN ('send_later', 'pick_send_date', 'message_scheduled', 'scheduled_messages', 'cancel_scheduled', 'send_at_out_of_range');
+
+DROP TABLE SCHEDULED_MESSAGE;
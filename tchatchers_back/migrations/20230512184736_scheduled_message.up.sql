@@ -0,0 +1,34 @@
+-- Add up migration script here
+CREATE TABLE SCHEDULED_MESSAGE (
+    uuid UUID PRIMARY KEY,
+    author INTEGER NOT NULL,
+    room VARCHAR NOT NULL,
+    content VARCHAR NOT NULL,
+    reply_to UUID,
+    send_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(author)
+        REFERENCES CHATTER(id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_scheduled_message_send_at ON SCHEDULED_MESSAGE(send_at);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('send_later', 'Send later'),
+('pick_send_date', 'Pick when the message has to be sent.'),
+('message_scheduled', 'Your message has been scheduled.'),
+('scheduled_messages', 'Scheduled messages'),
+('cancel_scheduled', 'Cancel'),
+('send_at_out_of_range', 'A message can only be scheduled within the next 30 days.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='send_later'), 2, 'Envoyer plus tard'),
+((SELECT id FROM label WHERE name='pick_send_date'), 2, 'Choisissez quand le message doit être envoyé.'),
+((SELECT id FROM label WHERE name='message_scheduled'), 2, 'Votre message a été programmé.'),
+((SELECT id FROM label WHERE name='scheduled_messages'), 2, 'Messages programmés'),
+((SELECT id FROM label WHERE name='cancel_scheduled'), 2, 'Annuler'),
+((SELECT id FROM label WHERE name='send_at_out_of_range'), 2, 'Un message ne peut être programmé que dans les 30 prochains jours.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN ('new_messages', 'unread_elsewhere'));
+
+DELETE FROM LABEL WHERE name IN ('new_messages', 'unread_elsewhere');
+
+DROP TABLE READ_MARKER;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+CREATE TABLE READ_MARKER (
+    user_id INTEGER NOT NULL,
+    room VARCHAR NOT NULL,
+    last_read_uuid UUID NOT NULL,
+    last_read_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY(user_id, room),
+    CONSTRAINT fk_chatter
+        FOREIGN KEY(user_id)
+        REFERENCES CHATTER(id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_read_marker_room ON READ_MARKER(room);
+
+INSERT INTO LABEL(name, default_translation) VALUES
+('new_messages', 'New messages'),
+('unread_elsewhere', 'Unread')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='new_messages'), 2, 'Nouveaux messages'),
+((SELECT id FROM label WHERE name='unread_elsewhere'), 2, 'Non lus')
+ON CONFLICT DO NOTHING;
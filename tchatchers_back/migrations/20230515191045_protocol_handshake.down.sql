@@ -0,0 +1,4 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name='protocol_mismatch');
+
+DELETE FROM LABEL WHERE name='protocol_mismatch';
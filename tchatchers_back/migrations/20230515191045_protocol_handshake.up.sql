@@ -0,0 +1,8 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('protocol_mismatch', 'A new version of the application is available, please reload the page.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='protocol_mismatch'), 2, 'Une nouvelle version de l’application est disponible, veuillez recharger la page.')
+ON CONFLICT DO NOTHING;
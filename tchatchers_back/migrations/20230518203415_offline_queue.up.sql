@@ -0,0 +1,8 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('message_queued', 'Queued')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='message_queued'), 2, 'En attente')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DELETE FROM TRANSLATION WHERE label_id IN (SELECT id FROM LABEL WHERE name IN ('ws_error_too_many_attachments', 'ws_error_unknown_attachments'));
+
+DELETE FROM LABEL WHERE name IN ('ws_error_too_many_attachments', 'ws_error_unknown_attachments');
@@ -0,0 +1,10 @@
+-- Add up migration script here
+INSERT INTO LABEL(name, default_translation) VALUES
+('ws_error_too_many_attachments', 'Too many files are attached to the message.'),
+('ws_error_unknown_attachments', 'Some of the files attached to the message can''t be found.')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO TRANSLATION(label_id, locale_id, translation) VALUES
+((SELECT id FROM label WHERE name='ws_error_too_many_attachments'), 2, 'Trop de fichiers sont joints au message.'),
+((SELECT id FROM label WHERE name='ws_error_unknown_attachments'), 2, 'Certains des fichiers joints au message sont introuvables.')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE SCHEDULED_MESSAGE DROP COLUMN claimed_at;
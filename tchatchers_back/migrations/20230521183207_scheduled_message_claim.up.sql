@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE SCHEDULED_MESSAGE ADD COLUMN claimed_at TIMESTAMPTZ;
@@ -0,0 +1,114 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Administrative filter tools.
+//!
+//! This module contains the routes allowing to manage the rules applied to the messages before they are sent, and
+//! to review the messages they flagged.
+
+use std::fmt::Display;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tchatchers_core::{
+    filter::{FilterManager, FilterRule, FlaggedMessage, InsertableFilterRule},
+    manager::ManagerError,
+    validation_error_message::ValidationErrorMessage,
+};
+use validator::Validate;
+
+use crate::{extractor::AdminExtractor, AppState};
+
+/// Reload the filter rules from the database.
+///
+/// This allows a refresh of the cache manager, useful when the rules have been modified in database.
+pub async fn reload_filter_rules(
+    AdminExtractor(_): AdminExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, ManagerError<impl Display>> {
+    let mut filter_manager = state.filter_manager.lock().await;
+    *filter_manager = FilterManager::init(&state.pg_pool).await;
+    filter_manager.get_rules()?;
+    Ok((StatusCode::OK, "Filter rules reloaded"))
+}
+
+/// Returns all the filter rules available in the cache manager.
+pub async fn get_filter_rules(
+    AdminExtractor(_): AdminExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, ManagerError<impl Display>> {
+    let filter_manager = state.filter_manager.lock().await;
+    let rules = filter_manager.get_rules()?;
+    Ok(Json(rules))
+}
+
+/// Creates a filter rule, applied to the messages sent from now on.
+///
+/// # Arguments
+///
+/// - rule : The rule to create.
+pub async fn create_filter_rule(
+    AdminExtractor(jwt): AdminExtractor,
+    State(state): State<AppState>,
+    Json(rule): Json<InsertableFilterRule>,
+) -> Result<impl IntoResponse, Response> {
+    if let Err(e) = rule.validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    match FilterRule::insert(&rule, jwt.user_id, &state.pg_pool).await {
+        Ok(rule) => {
+            *state.filter_manager.lock().await = FilterManager::init(&state.pg_pool).await;
+            Ok((StatusCode::CREATED, Json(rule)))
+        }
+        Err(e) => {
+            tracing::error!("An error happened while creating the filter rule : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Deletes a filter rule.
+///
+/// # Arguments
+///
+/// - rule_id : The rule to delete.
+pub async fn delete_filter_rule(
+    Path(rule_id): Path<i32>,
+    AdminExtractor(_): AdminExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    match FilterRule::delete(rule_id, &state.pg_pool).await {
+        Ok(true) => {
+            *state.filter_manager.lock().await = FilterManager::init(&state.pg_pool).await;
+            Ok((StatusCode::OK, "The filter rule has been deleted"))
+        }
+        Ok(false) => {
+            Err((StatusCode::NOT_FOUND, "This filter rule doesn't exist.").into_response())
+        }
+        Err(e) => {
+            tracing::error!("An error happened while deleting the filter rule : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Returns the messages flagged by the filter rules, from the most recent to the oldest.
+pub async fn get_flagged_messages(
+    AdminExtractor(_): AdminExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    match FlaggedMessage::find_all(&state.pg_pool).await {
+        Ok(messages) => Ok(Json(messages)),
+        Err(e) => {
+            tracing::error!(
+                "An error happened while retrieving the flagged messages : {:?}",
+                e
+            );
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
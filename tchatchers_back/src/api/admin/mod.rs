@@ -6,4 +6,5 @@
 //! None of the routes below should be accessible to the users since they can contain either sensitive informations or allow
 //! administrative operations.
 
+pub(crate) mod filter;
 pub(crate) mod translation;
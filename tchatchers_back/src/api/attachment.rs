@@ -0,0 +1,195 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Gathers the routes used to upload and download the files joined to the
+//! messages, along with the task removing the files no longer needed.
+
+use std::time::Duration;
+
+use crate::{extractor::JwtUserExtractor, AppState};
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tchatchers_core::{
+    attachment::{detect_mime_type, Attachment, MAX_ATTACHMENT_SIZE},
+    direct_message::can_access_room,
+};
+use tokio::fs;
+use uuid::Uuid;
+
+/// The directory the attachments are stored in.
+///
+/// It isn't served as the static assets are, the attachments being only
+/// downloadable by the users allowed to access the room of their message.
+const ATTACHMENTS_DIRECTORY: &str = "./attachments";
+
+/// The delay after which an attachment that hasn't been joined to any message
+/// is removed.
+const UNLINKED_ATTACHMENT_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+/// The delay between two removals of the attachments no longer needed.
+const PURGE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// The query parameters describing the uploaded file.
+#[derive(Debug, Deserialize)]
+pub struct AttachmentUploadQuery {
+    /// The name of the file.
+    name: String,
+}
+
+/// Uploads a file meant to be joined to a message.
+///
+/// The type of the file is detected from its content, and is rejected if it
+/// isn't among the ones accepted.
+///
+/// # Arguments
+///
+/// - query : The description of the file.
+/// - jwt : The authenticated user.
+/// - body : The file content.
+pub async fn upload_attachment(
+    Query(query): Query<AttachmentUploadQuery>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+    body: Bytes,
+) -> Result<impl IntoResponse, Response> {
+    if body.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "The file is empty.").into_response());
+    } else if body.len() > MAX_ATTACHMENT_SIZE {
+        return Err((StatusCode::PAYLOAD_TOO_LARGE, "The file is too large.").into_response());
+    }
+    let Some(mime_type) = detect_mime_type(&body) else {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "This type of file can't be attached.",
+        )
+            .into_response());
+    };
+    let attachment = Attachment {
+        id: Uuid::new_v4(),
+        file_name: query.name.chars().take(255).collect(),
+        mime_type: mime_type.into(),
+        size: body.len() as i64,
+        checksum: Sha256::digest(&body)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect(),
+    };
+    // The attachment is recorded before the file is written, so that the
+    // purge never mistakes the file for an orphan.
+    if let Err(e) = attachment.insert(jwt.user_id, &state.pg_pool).await {
+        tracing::error!("An error happened while saving the attachment : {:?}", e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response());
+    }
+    let file_path = format!("{ATTACHMENTS_DIRECTORY}/{}", attachment.stored_file_name());
+    if let Err(e) = fs::create_dir_all(ATTACHMENTS_DIRECTORY).await {
+        tracing::error!("The attachments directory couldn't be created : {:?}", e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response());
+    }
+    if let Err(e) = fs::write(&file_path, &body).await {
+        tracing::error!("An error happened while writing the attachment : {:?}", e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response());
+    }
+    Ok(Json(attachment))
+}
+
+/// Downloads a file joined to a message.
+///
+/// # Arguments
+///
+/// - id : The attachment identifier.
+/// - jwt : The authenticated user.
+pub async fn download_attachment(
+    Path(id): Path<Uuid>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let attachment = match Attachment::find_with_room(id, &state.pg_pool).await {
+        Ok(Some((attachment, room))) if can_access_room(&room, jwt.user_id) => attachment,
+        Ok(Some(_)) => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "You aren't allowed to access this conversation.",
+            )
+                .into_response())
+        }
+        Ok(None) => return Err((StatusCode::NOT_FOUND, "The file can't be found.").into_response()),
+        Err(e) => {
+            tracing::error!(
+                "An error happened while retrieving the attachment : {:?}",
+                e
+            );
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response());
+        }
+    };
+    let file_path = format!("{ATTACHMENTS_DIRECTORY}/{}", attachment.stored_file_name());
+    match fs::read(&file_path).await {
+        Ok(content) => Ok((
+            [
+                (header::CONTENT_TYPE, attachment.mime_type),
+                (header::X_CONTENT_TYPE_OPTIONS, "nosniff".into()),
+            ],
+            content,
+        )),
+        Err(e) => {
+            tracing::error!("The file {} couldn't be read : {:?}", file_path, e);
+            Err((StatusCode::NOT_FOUND, "The file can't be found.").into_response())
+        }
+    }
+}
+
+/// Periodically removes the attachments never joined to a message, and the
+/// files whose attachment no longer exists, such as the ones of the deleted
+/// messages.
+///
+/// # Arguments
+///
+/// - pool : The connection pool.
+pub async fn purge_attachments(pool: sqlx::PgPool) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let uploaded_before =
+            chrono::Utc::now() - chrono::Duration::from_std(UNLINKED_ATTACHMENT_EXPIRY).unwrap();
+        if let Err(e) = Attachment::delete_unlinked(uploaded_before, &pool).await {
+            tracing::error!("An error happened while removing the attachments : {:?}", e);
+            continue;
+        }
+        let mut stored: Vec<(Uuid, std::path::PathBuf)> = vec![];
+        let Ok(mut entries) = fs::read_dir(ATTACHMENTS_DIRECTORY).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Uuid::parse_str(stem).ok())
+            {
+                stored.push((id, path));
+            }
+        }
+        let ids: Vec<Uuid> = stored.iter().map(|(id, _)| *id).collect();
+        match Attachment::find_existing_ids(&ids, &pool).await {
+            Ok(existing) => {
+                for (_, path) in stored.iter().filter(|(id, _)| !existing.contains(id)) {
+                    if let Err(e) = fs::remove_file(path).await {
+                        tracing::error!("The file {:?} couldn't be removed : {:?}", path, e);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!(
+                    "An error happened while retrieving the attachments : {:?}",
+                    e
+                )
+            }
+        }
+    }
+}
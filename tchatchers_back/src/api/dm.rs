@@ -0,0 +1,70 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Gathers the routes used to browse the direct conversations of a user.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tchatchers_core::{direct_message::DirectConversation, user::PartialUser};
+
+use crate::{extractor::JwtUserExtractor, AppState};
+
+/// Returns the direct conversations of the user, from the most recently
+/// active to the oldest, along with their last message.
+///
+/// # Arguments
+///
+/// - jwt : The authenticated user.
+pub async fn get_direct_conversations(
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    match DirectConversation::list_for_user(jwt.user_id, &state.pg_pool).await {
+        Ok(conversations) => Ok(Json(conversations)),
+        Err(e) => {
+            tracing::error!(
+                "An error happened while retrieving the conversations : {:?}",
+                e
+            );
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Returns the direct conversation between the user and the one bound to the
+/// given login.
+///
+/// # Arguments
+///
+/// - login : The login of the interlocutor.
+/// - jwt : The authenticated user.
+pub async fn get_direct_conversation(
+    Path(login): Path<String>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let interlocutor = match PartialUser::find_by_login(&login, &state.pg_pool).await {
+        Ok(Some(interlocutor)) if interlocutor.is_authorized => interlocutor,
+        Ok(_) => {
+            return Err((StatusCode::NOT_FOUND, "This user doesn't exist.").into_response());
+        }
+        Err(e) => {
+            tracing::error!("An error happened while retrieving the user : {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response());
+        }
+    };
+    match DirectConversation::between(jwt.user_id, interlocutor, &state.pg_pool).await {
+        Ok(conversation) => Ok(Json(conversation)),
+        Err(e) => {
+            tracing::error!(
+                "An error happened while retrieving the conversation : {:?}",
+                e
+            );
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Gathers the routes used to search the messages and browse their details.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tchatchers_core::{
+    direct_message::can_access_room,
+    validation_error_message::ValidationErrorMessage,
+    ws_message::{MessageRevision, MessageSearchQuery, WsMessageContent},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{extractor::JwtUserExtractor, AppState};
+
+/// Returns the previous versions of an edited message, from the most recent to
+/// the oldest.
+///
+/// # Arguments
+///
+/// - uuid : The message identifier.
+/// - jwt : The authenticated user.
+pub async fn get_message_revisions(
+    Path(uuid): Path<Uuid>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    match WsMessageContent::find_room(uuid, &state.pg_pool).await {
+        Ok(Some(room)) if can_access_room(&room, jwt.user_id) => {}
+        Ok(Some(_)) => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "You aren't allowed to access this conversation.",
+            )
+                .into_response())
+        }
+        Ok(None) => {
+            return Err((StatusCode::NOT_FOUND, "The message can't be found.").into_response())
+        }
+        Err(e) => {
+            tracing::error!("An error happened while retrieving the message : {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response());
+        }
+    }
+    match MessageRevision::find_for_message(uuid, &state.pg_pool).await {
+        Ok(revisions) => Ok(Json(revisions)),
+        Err(e) => {
+            tracing::error!("An error happened while retrieving the revisions : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Searches for the messages matching the query within the rooms the user
+/// took part in.
+///
+/// # Arguments
+///
+/// - query : The search criteria.
+/// - jwt : The authenticated user.
+pub async fn search_messages(
+    Query(query): Query<MessageSearchQuery>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    if let Err(e) = query.validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    match WsMessageContent::search(&query, Some(jwt.user_id), &state.pg_pool).await {
+        Ok(messages) => Ok(Json(messages)),
+        Err(e) => {
+            tracing::error!("An error happened while searching the messages : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
@@ -4,7 +4,12 @@
 //! Defines the set of API entrypoints that can be called on client side.
 
 pub(crate) mod admin;
+pub(crate) mod attachment;
+pub(crate) mod dm;
 pub(crate) mod locale;
+pub(crate) mod message;
 pub(crate) mod pfp;
+pub(crate) mod room;
+pub(crate) mod scheduled_message;
 pub(crate) mod user;
 pub(crate) mod user_context;
@@ -0,0 +1,165 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Gathers the routes used to browse the content of the rooms.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tchatchers_core::{
+    direct_message::can_access_room,
+    pin::PinnedMessage,
+    read_marker::UnreadCount,
+    room::{RoomMember, RoomNameValidator},
+    validation_error_message::ValidationErrorMessage,
+    ws_message::{MessageCursor, WsMessageContent, DEFAULT_PAGE_SIZE},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{extractor::JwtUserExtractor, AppState};
+
+/// The query parameters used to paginate through the history of a room.
+///
+/// Both the timestamp and the uuid of the oldest known message are required
+/// to build the cursor, the most recent messages are returned otherwise.
+#[derive(Debug, Deserialize)]
+pub struct MessagePageQuery {
+    /// The timestamp of the oldest message known by the client.
+    before_timestamp: Option<DateTime<Utc>>,
+    /// The uuid of the oldest message known by the client.
+    before_uuid: Option<Uuid>,
+    /// The number of messages requested.
+    page_size: Option<i64>,
+}
+
+impl MessagePageQuery {
+    /// Returns the cursor described by the query, if any.
+    fn cursor(&self) -> Option<MessageCursor> {
+        match (self.before_timestamp, self.before_uuid) {
+            (Some(timestamp), Some(uuid)) => Some(MessageCursor { timestamp, uuid }),
+            _ => None,
+        }
+    }
+}
+
+/// Returns a page of the room's history, from the most recent message to the
+/// oldest.
+///
+/// # Arguments
+///
+/// - room : The room name.
+/// - query : The pagination parameters.
+pub async fn get_messages_page(
+    Path(room): Path<String>,
+    Query(query): Query<MessagePageQuery>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    if let Err(e) = RoomNameValidator::from(room.clone()).validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    if !can_access_room(&room, jwt.user_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You aren't allowed to access this conversation.",
+        )
+            .into_response());
+    }
+    match WsMessageContent::query_page_for_room(
+        &room,
+        query.cursor(),
+        query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+        &state.pg_pool,
+    )
+    .await
+    {
+        Ok(page) => Ok(Json(page)),
+        Err(e) => {
+            tracing::error!("An error happened while retrieving the messages : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Returns the users currently connected to the room.
+///
+/// # Arguments
+///
+/// - room : The room name.
+pub async fn get_room_members(
+    Path(room): Path<String>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    if let Err(e) = RoomNameValidator::from(room.clone()).validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    if !can_access_room(&room, jwt.user_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You aren't allowed to access this conversation.",
+        )
+            .into_response());
+    }
+    let members: Vec<RoomMember> = state
+        .txs
+        .lock()
+        .await
+        .get(&room)
+        .map(|ws_room| ws_room.members())
+        .unwrap_or_default();
+    Ok(Json(members))
+}
+
+/// Returns the messages pinned in the room, from the most recently pinned to
+/// the oldest.
+///
+/// # Arguments
+///
+/// - room : The room name.
+pub async fn get_room_pins(
+    Path(room): Path<String>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    if let Err(e) = RoomNameValidator::from(room.clone()).validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    if !can_access_room(&room, jwt.user_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You aren't allowed to access this conversation.",
+        )
+            .into_response());
+    }
+    match PinnedMessage::find_for_room(&room, &state.pg_pool).await {
+        Ok(pins) => Ok(Json(pins)),
+        Err(e) => {
+            tracing::error!("An error happened while retrieving the pins : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Returns the number of unread messages in each room the user follows.
+pub async fn get_unread_counts(
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    match UnreadCount::find_for_user(jwt.user_id, &state.pg_pool).await {
+        Ok(counts) => Ok(Json(counts)),
+        Err(e) => {
+            tracing::error!(
+                "An error happened while retrieving the unread counts : {:?}",
+                e
+            );
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Gathers the routes used to schedule messages, along with the task sending
+//! them once they are due.
+
+use std::time::Duration;
+
+use crate::{
+    extractor::JwtUserExtractor,
+    ws::{filter_content, publish_message},
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tchatchers_core::{
+    direct_message::can_access_room,
+    room::RoomNameValidator,
+    scheduled_message::{InsertableScheduledMessage, ScheduledMessage},
+    user::PartialUser,
+    validation_error_message::ValidationErrorMessage,
+    ws_message::{WsError, WsMessage, WsMessageContent},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+/// The delay between two lookups of the messages due.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The maximum number of messages sent at each lookup.
+const SCHEDULER_BATCH_SIZE: i64 = 100;
+
+/// Returns the messages the user scheduled in the room.
+///
+/// # Arguments
+///
+/// - room : The room name.
+pub async fn get_scheduled_messages(
+    Path(room): Path<String>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    if let Err(e) = RoomNameValidator::from(room.clone()).validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    match ScheduledMessage::find_for_author_in_room(jwt.user_id, &room, &state.pg_pool).await {
+        Ok(messages) => Ok(Json(messages)),
+        Err(e) => {
+            tracing::error!(
+                "An error happened while retrieving the scheduled messages : {:?}",
+                e
+            );
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Schedules a message to be sent in the room.
+///
+/// # Arguments
+///
+/// - room : The room name.
+/// - message : The message to schedule.
+pub async fn schedule_message(
+    Path(room): Path<String>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+    Json(message): Json<InsertableScheduledMessage>,
+) -> Result<impl IntoResponse, Response> {
+    if let Err(e) = RoomNameValidator::from(room.clone()).validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    if !can_access_room(&room, jwt.user_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You aren't allowed to access this conversation.",
+        )
+            .into_response());
+    }
+    if let Err(e) = message.validate() {
+        return Err(ValidationErrorMessage::from(e).into_response());
+    }
+    match message.insert(jwt.user_id, &room, &state.pg_pool).await {
+        Ok(Some(scheduled)) => Ok((StatusCode::CREATED, Json(scheduled))),
+        Ok(None) => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            "You have too many messages waiting to be sent.",
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!("An error happened while scheduling a message : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Cancels a message the user scheduled.
+///
+/// # Arguments
+///
+/// - uuid : The message to cancel.
+pub async fn cancel_scheduled_message(
+    Path(uuid): Path<Uuid>,
+    JwtUserExtractor(jwt): JwtUserExtractor,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    match ScheduledMessage::cancel(uuid, jwt.user_id, &state.pg_pool).await {
+        Ok(true) => Ok((StatusCode::OK, "The message has been cancelled")),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            "This message doesn't exist or has already been sent.",
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!("An error happened while cancelling a message : {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response())
+        }
+    }
+}
+
+/// Periodically sends the scheduled messages that are due.
+///
+/// The messages go through the same filters as the ones sent over the
+/// websocket, their authors being informed if they are refused. The messages
+/// that can't be sent because of a transient error are kept, and sent again
+/// at the next lookup.
+///
+/// # Arguments
+///
+/// - state : The data shared across threads.
+pub async fn send_scheduled_messages(state: AppState) {
+    let mut interval = tokio::time::interval(SCHEDULER_INTERVAL);
+    loop {
+        interval.tick().await;
+        let due = match ScheduledMessage::claim_due(SCHEDULER_BATCH_SIZE, &state.pg_pool).await {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::error!(
+                    "An error happened while retrieving the scheduled messages : {:?}",
+                    e
+                );
+                continue;
+            }
+        };
+        for scheduled in due {
+            let (uuid, author_id) = (scheduled.uuid, scheduled.author);
+            let author = match PartialUser::find_by_id(author_id, &state.pg_pool).await {
+                Ok(Some(author))
+                    if author.is_authorized && can_access_room(&scheduled.room, author.id) =>
+                {
+                    author
+                }
+                Ok(_) => {
+                    settle(uuid, true, &state).await;
+                    continue;
+                }
+                Err(e) => {
+                    tracing::error!("An error happened while retrieving the user : {:?}", e);
+                    settle(uuid, false, &state).await;
+                    continue;
+                }
+            };
+            let result =
+                match filter_content(scheduled.uuid, &scheduled.content, &state.filter_manager)
+                    .await
+                {
+                    Ok((content, flagged_by)) => {
+                        let ws_message = WsMessageContent {
+                            uuid: scheduled.uuid,
+                            content,
+                            author,
+                            room: scheduled.room,
+                            reply_to: scheduled.reply_to,
+                            ..WsMessageContent::default()
+                        };
+                        publish_message(ws_message, &flagged_by, &state).await
+                    }
+                    Err(error) => Err(error),
+                };
+            match result {
+                Ok(_) => settle(uuid, true, &state).await,
+                // The message couldn't be saved or checked, it is sent again
+                // later on.
+                Err(WsError::MessageNotPersisted(_)) => settle(uuid, false, &state).await,
+                Err(error) => {
+                    settle(uuid, true, &state).await;
+                    state
+                        .users
+                        .lock()
+                        .await
+                        .send(author_id, &WsMessage::Error(error));
+                }
+            }
+        }
+    }
+}
+
+/// Removes a claimed message once it has been sent or refused, or releases
+/// it to send it again later on.
+///
+/// # Arguments
+///
+/// - uuid : The claimed message.
+/// - is_done : Whether the message has been sent or refused.
+/// - state : The data shared across threads.
+async fn settle(uuid: Uuid, is_done: bool, state: &AppState) {
+    let result = match is_done {
+        true => ScheduledMessage::delete(uuid, &state.pg_pool).await,
+        false => ScheduledMessage::release(uuid, &state.pg_pool).await,
+    };
+    if let Err(e) = result {
+        tracing::error!(
+            "An error happened while settling a scheduled message : {:?}",
+            e
+        );
+    }
+}
@@ -9,17 +9,39 @@
 
 pub mod api;
 pub mod extractor;
+pub mod retention;
 pub mod validator;
 pub mod ws;
 
+use api::admin::filter::create_filter_rule;
+use api::admin::filter::delete_filter_rule;
+use api::admin::filter::get_filter_rules;
+use api::admin::filter::get_flagged_messages;
+use api::admin::filter::reload_filter_rules;
 use api::admin::translation::get_all_translations;
 use api::admin::translation::get_translations_for_locale;
 use api::admin::translation::reload_translations;
+use api::attachment::download_attachment;
+use api::attachment::purge_attachments;
+use api::attachment::upload_attachment;
+use api::dm::get_direct_conversation;
+use api::dm::get_direct_conversations;
 use api::locale::get_locale_id;
 use api::locale::get_locales;
+use api::message::get_message_revisions;
+use api::message::search_messages;
 use api::pfp::*;
+use api::room::get_messages_page;
+use api::room::get_room_members;
+use api::room::get_room_pins;
+use api::room::get_unread_counts;
+use api::scheduled_message::cancel_scheduled_message;
+use api::scheduled_message::get_scheduled_messages;
+use api::scheduled_message::schedule_message;
+use api::scheduled_message::send_scheduled_messages;
 use api::user::*;
 use api::user_context::user_context;
+use axum::extract::DefaultBodyLimit;
 use axum::http::header::AUTHORIZATION;
 use axum::http::header::COOKIE;
 use axum::http::header::SEC_WEBSOCKET_PROTOCOL;
@@ -27,15 +49,18 @@ use axum::routing::get_service;
 use axum::routing::put;
 use axum::{
     http::StatusCode,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use r2d2::Pool;
 use redis::Client;
+use retention::purge_expired_messages;
 use sqlx_core::postgres::PgPool;
 use std::iter::once;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use tchatchers_core::attachment::MAX_ATTACHMENT_SIZE;
+use tchatchers_core::filter::FilterManager;
 use tchatchers_core::locale::LocaleManager;
 use tchatchers_core::navlink::NavlinkManager;
 use tchatchers_core::translation::TranslationManager;
@@ -54,6 +79,7 @@ use tracing::Level;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use ws::ws_handler;
 use ws::WsRooms;
+use ws::WsUsers;
 
 const REFRESH_TOKEN_PATH: &str = "refresh_token";
 
@@ -66,6 +92,8 @@ pub struct AppState {
     refresh_token_secret: String,
     /// The WS rooms, with the key being the room name.
     txs: Arc<Mutex<WsRooms>>,
+    /// The WS connections, with the key being the user id.
+    users: Arc<Mutex<WsUsers>>,
     /// The Postgres pool.
     pg_pool: PgPool,
     /// The translation manager.
@@ -80,6 +108,10 @@ pub struct AppState {
     ///
     /// Used to cache the locales from the database.
     locale_manager: LocaleManager,
+    /// The filter manager.
+    ///
+    /// Used to cache the filter rules from the database.
+    filter_manager: Arc<Mutex<FilterManager>>,
     /// Redis connection pool
     redis_pool: Pool<Client>,
 }
@@ -108,12 +140,23 @@ async fn main() {
         locale_manager: LocaleManager::init(&pg_pool).await,
         navlink_manager: Arc::new(Mutex::new(NavlinkManager::init(&pg_pool).await)),
         translation_manager: Arc::new(Mutex::new(TranslationManager::init(&pg_pool).await)),
+        filter_manager: Arc::new(Mutex::new(FilterManager::init(&pg_pool).await)),
         jwt_secret,
         txs: Arc::new(Mutex::new(WsRooms::default())),
+        users: Arc::new(Mutex::new(WsUsers::default())),
         pg_pool,
         redis_pool,
     };
 
+    tokio::spawn(ws::listen_to_deletions(
+        shared_state.txs.clone(),
+        shared_state.pg_pool.clone(),
+    ));
+    tokio::spawn(ws::expire_typing_indicators(shared_state.txs.clone()));
+    tokio::spawn(purge_attachments(shared_state.pg_pool.clone()));
+    tokio::spawn(purge_expired_messages(shared_state.pg_pool.clone()));
+    tokio::spawn(send_scheduled_messages(shared_state.clone()));
+
     let app = Router::new()
         .route(
             "/api/user",
@@ -127,9 +170,27 @@ async fn main() {
         .route("/api/logout", get(logout))
         .route("/api/validate", get(validate))
         .route("/api/pfp", post(upload_pfp))
+        .route(
+            "/api/attachment",
+            post(upload_attachment).layer(DefaultBodyLimit::max(MAX_ATTACHMENT_SIZE)),
+        )
         .route("/api/app_context", get(user_context))
         .route("/api/locale/", get(get_locales))
         .route("/api/locale/:locale_id", get(get_locale_id))
+        .route("/api/room/:room/messages", get(get_messages_page))
+        .route("/api/room/:room/members", get(get_room_members))
+        .route("/api/room/:room/pins", get(get_room_pins))
+        .route("/api/rooms/unread", get(get_unread_counts))
+        .route(
+            "/api/room/:room/scheduled",
+            get(get_scheduled_messages).post(schedule_message),
+        )
+        .route("/api/scheduled/:uuid", delete(cancel_scheduled_message))
+        .route("/api/attachment/:id", get(download_attachment))
+        .route("/api/message/:uuid/revisions", get(get_message_revisions))
+        .route("/api/search", get(search_messages))
+        .route("/api/dm", get(get_direct_conversations))
+        .route("/api/dm/:login", get(get_direct_conversation))
         .route(
             "/api/admin/translation",
             put(reload_translations).get(get_all_translations),
@@ -138,6 +199,14 @@ async fn main() {
             "/api/admin/translation/:locale_id",
             get(get_translations_for_locale),
         )
+        .route(
+            "/api/admin/filter",
+            get(get_filter_rules)
+                .post(create_filter_rule)
+                .put(reload_filter_rules),
+        )
+        .route("/api/admin/filter/flagged", get(get_flagged_messages))
+        .route("/api/admin/filter/:rule_id", delete(delete_filter_rule))
         .route("/ws/:room", get(ws_handler))
         .nest_service(
             "/static",
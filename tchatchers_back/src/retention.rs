@@ -0,0 +1,50 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Purges the messages exceeding the retention policy of their room.
+
+use std::time::Duration;
+
+use tchatchers_core::retention::RetentionPolicy;
+
+/// The delay between two purges.
+const PURGE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// The maximum number of messages removed at once, so that the tables aren't
+/// locked for too long.
+const PURGE_BATCH_SIZE: i64 = 500;
+
+/// The pause between two batches, leaving room for the other statements.
+const PAUSE_BETWEEN_BATCHES: Duration = Duration::from_millis(200);
+
+/// Periodically purges the expired messages, batch after batch until none is
+/// left.
+///
+/// # Arguments
+///
+/// - pool : The connection pool.
+pub async fn purge_expired_messages(pool: sqlx::PgPool) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let mut purged: u64 = 0;
+        loop {
+            match RetentionPolicy::purge_batch(PURGE_BATCH_SIZE, &pool).await {
+                Ok(count) => {
+                    purged += count;
+                    if count < PURGE_BATCH_SIZE as u64 {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("An error happened while purging the messages : {:?}", e);
+                    break;
+                }
+            }
+            tokio::time::sleep(PAUSE_BETWEEN_BATCHES).await;
+        }
+        if purged > 0 {
+            tracing::info!("{} expired messages have been purged", purged);
+        }
+    }
+}
@@ -8,31 +8,206 @@
 use std::{
     collections::HashMap,
     ops::{Deref, DerefMut},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::AppState;
 use axum::{
-    extract::{ws::Message, ws::WebSocket, Path, State, WebSocketUpgrade},
+    extract::{ws::CloseFrame, ws::Message, ws::WebSocket, Path, State, WebSocketUpgrade},
     http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
+use chrono::Utc;
 use futures_util::{SinkExt, StreamExt};
+use r2d2::Pool;
+use redis::Client;
 use tchatchers_core::{
+    attachment::{Attachment, MAX_ATTACHMENTS_PER_MESSAGE},
     authorization_token::AuthorizationToken,
-    room::RoomNameValidator,
+    codec::{from_msgpack, EncodedMessage, WsCodec},
+    direct_message::{can_access_room, dm_participants},
+    filter::{FilterManager, FilterOutcome, FilterRule},
+    mention::{mentioned_logins, persist_mentions},
+    pin::{can_pin, PinOutcome, PinnedMessage},
+    protocol::{
+        is_compatible, negotiate, Capability, PROTOCOL_VERSION, WS_CLOSE_INCOMPATIBLE_PROTOCOL,
+    },
+    rate_limit::RateLimit,
+    reaction::MessageReaction,
+    read_marker::ReadMarker,
+    receipt::MessageReceipt,
+    room::{RoomMember, RoomNameValidator},
     serializable_token::SerializableToken,
+    user::PartialUser,
     validation_error_message::ValidationErrorMessage,
-    ws_message::{WsMessage, WsMessageContent, WsReceptionStatus},
+    ws_message::{
+        MessagePreview, MessagesDeletion, PersistOutcome, TypingUser, WsError, WsMessage,
+        WsMessageContent, WsReceptionStatus, MESSAGES_DELETED_CHANNEL, TYPING_EXPIRY_SECONDS,
+    },
 };
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use uuid::Uuid;
 use validator::Validate;
 
+/// The minimum delay between two typing notifications processed for the same
+/// connection.
+const TYPING_THROTTLE: Duration = Duration::from_secs(1);
+
+/// The delay within which a client has to open the connection with a `Hello`
+/// message.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The data shared by the connections of a room.
+#[derive(Debug)]
+pub struct WsRoom {
+    /// The sender used to broadcast the messages to the room.
+    pub tx: broadcast::Sender<EncodedMessage>,
+    /// The users currently typing, with their name and the moment their
+    /// indicator expires.
+    typing: HashMap<i32, (String, Instant)>,
+    /// The users connected to the room.
+    members: HashMap<i32, RoomMember>,
+}
+
+impl Default for WsRoom {
+    fn default() -> Self {
+        let (tx, _rx) = broadcast::channel(1000);
+        Self {
+            tx,
+            typing: HashMap::new(),
+            members: HashMap::new(),
+        }
+    }
+}
+
+impl WsRoom {
+    /// Sends a message to every client connected to the room.
+    ///
+    /// # Arguments
+    ///
+    /// - msg : The message to send.
+    pub fn broadcast(&self, msg: &WsMessage) {
+        let _ = self.tx.send(EncodedMessage::new(msg));
+    }
+
+    /// Informs the room of the users currently typing.
+    fn broadcast_typing(&self) {
+        let mut users: Vec<TypingUser> = self
+            .typing
+            .iter()
+            .map(|(user_id, (name, _))| TypingUser {
+                user_id: *user_id,
+                name: name.clone(),
+            })
+            .collect();
+        users.sort_by_key(|user| user.user_id);
+        self.broadcast(&WsMessage::UsersTyping(users));
+    }
+
+    /// Marks the user as typing, and informs the room if they weren't
+    /// already.
+    ///
+    /// # Arguments
+    ///
+    /// - user : The user typing.
+    fn start_typing(&mut self, user: &PartialUser) {
+        let expires_at = Instant::now() + Duration::from_secs(TYPING_EXPIRY_SECONDS);
+        if self
+            .typing
+            .insert(user.id, (user.name.clone(), expires_at))
+            .is_none()
+        {
+            self.broadcast_typing();
+        }
+    }
+
+    /// Removes the user from the ones typing, and informs the room if they
+    /// were.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user that stopped typing.
+    fn stop_typing(&mut self, user_id: i32) {
+        if self.typing.remove(&user_id).is_some() {
+            self.broadcast_typing();
+        }
+    }
+
+    /// Returns the users connected to the room, from the earliest to join to
+    /// the latest.
+    pub fn members(&self) -> Vec<RoomMember> {
+        let mut members: Vec<RoomMember> = self.members.values().cloned().collect();
+        members.sort_by_key(|member| (member.joined_at, member.user_id));
+        members
+    }
+
+    /// Returns the ids of the connected users, except the given one.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user excluded.
+    fn other_members(&self, user_id: i32) -> Vec<i32> {
+        self.members
+            .keys()
+            .copied()
+            .filter(|member_id| *member_id != user_id)
+            .collect()
+    }
+
+    /// Registers a new connection of the user, and informs the room if they
+    /// weren't connected yet.
+    ///
+    /// # Arguments
+    ///
+    /// - user : The user connecting.
+    fn join(&mut self, user: &PartialUser) {
+        let member = self
+            .members
+            .entry(user.id)
+            .or_insert_with(|| RoomMember::from(user));
+        member.connections += 1;
+        if member.connections == 1 {
+            self.broadcast(&WsMessage::PresenceChanged(self.members()));
+        }
+    }
+
+    /// Unregisters a connection of the user, and informs the room if it was
+    /// their last one.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user disconnecting.
+    fn leave(&mut self, user_id: i32) {
+        let Some(member) = self.members.get_mut(&user_id) else {
+            return;
+        };
+        member.connections -= 1;
+        if member.connections == 0 {
+            self.members.remove(&user_id);
+            self.stop_typing(user_id);
+            self.broadcast(&WsMessage::PresenceChanged(self.members()));
+        }
+    }
+
+    /// Removes the typing indicators that haven't been renewed in time, and
+    /// informs the room if any did.
+    fn expire_typing(&mut self) {
+        let now = Instant::now();
+        let typing_count = self.typing.len();
+        self.typing.retain(|_, (_, expires_at)| *expires_at > now);
+        if self.typing.len() != typing_count {
+            self.broadcast_typing();
+        }
+    }
+}
+
 /// Hashmap that contains the room name as key and the websocket data as value.
 #[derive(Default, Debug)]
-pub struct WsRooms(HashMap<String, broadcast::Sender<String>>);
+pub struct WsRooms(HashMap<String, WsRoom>);
 
 impl Deref for WsRooms {
-    type Target = HashMap<String, broadcast::Sender<String>>;
+    type Target = HashMap<String, WsRoom>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -45,6 +220,146 @@ impl DerefMut for WsRooms {
     }
 }
 
+/// A connection opened by a user.
+#[derive(Debug)]
+struct WsConnection {
+    /// The sender dedicated to the connection.
+    tx: mpsc::Sender<EncodedMessage>,
+    /// The capabilities negotiated when the connection has been opened.
+    capabilities: Vec<Capability>,
+}
+
+impl WsConnection {
+    /// Whether the client negotiated the capability required by the message,
+    /// if any.
+    ///
+    /// # Arguments
+    ///
+    /// - msg : The message to send.
+    fn supports(&self, msg: &WsMessage) -> bool {
+        match msg.required_capability() {
+            Some(capability) => self.capabilities.contains(&capability),
+            None => true,
+        }
+    }
+}
+
+/// The connections opened by each user, whatever the room they are connected
+/// to.
+///
+/// Used to reach a user outside of the room they are chatting in.
+#[derive(Default, Debug)]
+pub struct WsUsers(HashMap<i32, Vec<WsConnection>>);
+
+impl WsUsers {
+    /// Registers a connection of the user.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user connecting.
+    /// - tx : The sender dedicated to the connection.
+    /// - capabilities : The capabilities negotiated for the connection.
+    fn register(
+        &mut self,
+        user_id: i32,
+        tx: mpsc::Sender<EncodedMessage>,
+        capabilities: Vec<Capability>,
+    ) {
+        self.0
+            .entry(user_id)
+            .or_default()
+            .push(WsConnection { tx, capabilities });
+    }
+
+    /// Unregisters a connection of the user.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user disconnecting.
+    /// - tx : The sender dedicated to the connection.
+    fn unregister(&mut self, user_id: i32, tx: &mpsc::Sender<EncodedMessage>) {
+        if let Some(connections) = self.0.get_mut(&user_id) {
+            connections.retain(|connection| !connection.tx.same_channel(tx));
+            if connections.is_empty() {
+                self.0.remove(&user_id);
+            }
+        }
+    }
+
+    /// Sends a message to every connection of the user supporting it.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user to reach.
+    /// - msg : The message to send.
+    pub(crate) fn send(&self, user_id: i32, msg: &WsMessage) {
+        let mut encoded: Option<EncodedMessage> = None;
+        for connection in self.0.get(&user_id).into_iter().flatten() {
+            if connection.supports(msg) {
+                let encoded = encoded.get_or_insert_with(|| EncodedMessage::new(msg));
+                let _ = connection.tx.try_send(encoded.clone());
+            }
+        }
+    }
+}
+
+/// Listens to the message deletions notified by the database, and informs
+/// the rooms they belonged to.
+///
+/// Deletions can be made from the websockets as well as from the CLI, this
+/// way every connected client is informed no matter where they come from.
+///
+/// # Arguments
+///
+/// - txs : The WS rooms.
+/// - pool : The connection pool.
+pub async fn listen_to_deletions(txs: Arc<Mutex<WsRooms>>, pool: sqlx::PgPool) {
+    let mut listener = match sqlx::postgres::PgListener::connect_with(&pool).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Couldn't listen to the message deletions : {:?}", e);
+            return;
+        }
+    };
+    if let Err(e) = listener.listen(MESSAGES_DELETED_CHANNEL).await {
+        tracing::error!("Couldn't listen to the message deletions : {:?}", e);
+        return;
+    }
+    loop {
+        match listener.recv().await {
+            Ok(notification) => {
+                let Ok(deletion) = serde_json::from_str::<MessagesDeletion>(notification.payload()) else {
+                    tracing::error!("Unexpected deletion payload : {}", notification.payload());
+                    continue;
+                };
+                if let Some(ws_room) = txs.lock().await.get(&deletion.room) {
+                    ws_room.broadcast(&WsMessage::Deleted(deletion.messages));
+                }
+            }
+            Err(e) => tracing::error!(
+                "An error happened while listening to the deletions : {:?}",
+                e
+            ),
+        }
+    }
+}
+
+/// Periodically removes the typing indicators of the users that didn't renew
+/// them, so that a client leaving abruptly doesn't leave a stale indicator.
+///
+/// # Arguments
+///
+/// - txs : The WS rooms.
+pub async fn expire_typing_indicators(txs: Arc<Mutex<WsRooms>>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        for ws_room in txs.lock().await.values_mut() {
+            ws_room.expire_typing();
+        }
+    }
+}
+
 /// The HTTP entry point.
 ///
 /// # Arguments
@@ -62,7 +377,7 @@ pub async fn ws_handler(
     let Some(auth_header) = headers.get("Sec-WebSocket-Protocol") else {
         return Err((StatusCode::BAD_REQUEST, "Authentication header is required in order to access this service").into_response());
     };
-    if let Err(_e) = AuthorizationToken::decode(auth_header.to_str().unwrap(), &state.jwt_secret) {
+    let Ok(jwt) = AuthorizationToken::decode(auth_header.to_str().unwrap(), &state.jwt_secret) else {
         return Err((
             StatusCode::UNAUTHORIZED,
             "This route is protected, please authenticate prior accessing this.",
@@ -73,7 +388,404 @@ pub async fn ws_handler(
     if let Err(e) = room_name_validator.validate() {
         return Err(ValidationErrorMessage::from(e).into_response());
     }
-    Ok(ws.on_upgrade(|socket| handle_socket(socket, state, room)))
+    let user = match PartialUser::find_by_id(jwt.user_id, &state.pg_pool).await {
+        Ok(Some(user)) if user.is_authorized => user,
+        Ok(Some(_)) => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Your account is not authorized to access this service.",
+            )
+                .into_response())
+        }
+        Ok(None) => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "The user bound to this token doesn't exist anymore.",
+            )
+                .into_response())
+        }
+        Err(e) => {
+            tracing::error!("An error happened while retrieving the user : {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "An error happened").into_response());
+        }
+    };
+    if !can_access_room(&room, user.id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "You aren't allowed to access this conversation.",
+        )
+            .into_response());
+    }
+    Ok(ws.on_upgrade(|socket| handle_socket(socket, state, room, user)))
+}
+
+/// Sends a message to the client bound to the socket only.
+///
+/// # Arguments
+///
+/// - tx : The sender dedicated to the client.
+/// - msg : The message to send.
+async fn reply(tx: &mpsc::Sender<EncodedMessage>, msg: &WsMessage) {
+    let _ = tx.send(EncodedMessage::new(msg)).await;
+}
+
+/// Informs the room that the reactions on a message changed.
+///
+/// # Arguments
+///
+/// - uuid : The message reacted to.
+/// - tx : The room's sender.
+/// - pool : The connection pool.
+async fn broadcast_reactions(
+    uuid: Uuid,
+    tx: &broadcast::Sender<EncodedMessage>,
+    pool: &sqlx::PgPool,
+) {
+    match MessageReaction::find_for_message(uuid, pool).await {
+        Ok(reactions) => {
+            let _ = tx.send(EncodedMessage::new(&WsMessage::ReactionsUpdated {
+                uuid,
+                reactions,
+            }));
+        }
+        Err(e) => tracing::error!("An error happened while retrieving the reactions : {:?}", e),
+    }
+}
+
+/// Informs the room that its pinned messages changed.
+///
+/// # Arguments
+///
+/// - room : The room name.
+/// - tx : The room's sender.
+/// - pool : The connection pool.
+async fn broadcast_pins(room: &str, tx: &broadcast::Sender<EncodedMessage>, pool: &sqlx::PgPool) {
+    match PinnedMessage::find_for_room(room, pool).await {
+        Ok(pins) => {
+            let _ = tx.send(EncodedMessage::new(&WsMessage::PinsUpdated(pins)));
+        }
+        Err(e) => tracing::error!("An error happened while retrieving the pins : {:?}", e),
+    }
+}
+
+/// Consumes a token from the user's bucket, and returns the delay to wait if
+/// the user is sending messages too fast.
+///
+/// The messages are let through when Redis can't be reached, so that an
+/// outage doesn't prevent the users from chatting.
+///
+/// # Arguments
+///
+/// - rate_limit : The limit applied to the user.
+/// - user_id : The user sending a message.
+/// - redis_pool : The Redis connection pool.
+fn throttle(rate_limit: &RateLimit, user_id: i32, redis_pool: &Pool<Client>) -> Option<Duration> {
+    let mut con = match redis_pool.get() {
+        Ok(con) => con,
+        Err(e) => {
+            tracing::error!("Couldn't get a Redis connection : {:?}", e);
+            return None;
+        }
+    };
+    match rate_limit.try_acquire(user_id, &mut con) {
+        Ok(retry_after) => retry_after,
+        Err(e) => {
+            tracing::error!("An error happened while applying the rate limit : {:?}", e);
+            None
+        }
+    }
+}
+
+/// Applies the filter rules to the content of a message.
+///
+/// Returns the content to persist along with the rules that flagged it, or
+/// the error to send back if the message is rejected. The message is refused
+/// as well if the rules aren't available, so that no content is persisted
+/// without being checked.
+///
+/// # Arguments
+///
+/// - uuid : The identifier of the message.
+/// - content : The content of the message.
+/// - filter_manager : The manager caching the filter rules.
+pub(crate) async fn filter_content(
+    uuid: Uuid,
+    content: &str,
+    filter_manager: &Mutex<FilterManager>,
+) -> Result<(String, Vec<i32>), WsError> {
+    match filter_manager.lock().await.apply(content) {
+        Ok(FilterOutcome::Rejected) => Err(WsError::MessageRejected(uuid)),
+        Ok(FilterOutcome::Accepted {
+            content,
+            flagged_by,
+        }) => Ok((content, flagged_by)),
+        Err(e) => {
+            tracing::error!("The filter rules couldn't be applied : {}", e);
+            Err(WsError::MessageNotPersisted(uuid))
+        }
+    }
+}
+
+/// Records the rules that flagged a message.
+///
+/// # Arguments
+///
+/// - uuid : The message flagged.
+/// - flagged_by : The rules that flagged the message.
+/// - pool : The connection pool.
+async fn flag_message(uuid: Uuid, flagged_by: &[i32], pool: &sqlx::PgPool) {
+    if flagged_by.is_empty() {
+        return;
+    }
+    if let Err(e) = FilterRule::flag_message(uuid, flagged_by, pool).await {
+        tracing::error!("An error happened while flagging a message : {:?}", e);
+    }
+}
+
+/// Persists a message and delivers it to the users connected to its room,
+/// notifying the users it mentions.
+///
+/// When nobody is connected to the room, the message is only persisted, the
+/// users retrieving it once they join. A message already persisted isn't
+/// delivered again.
+///
+/// Returns the acknowledgment to send to the author.
+///
+/// # Arguments
+///
+/// - ws_message : The message, whose author and room are already set.
+/// - flagged_by : The filter rules that flagged the message.
+/// - state : The data shared across threads.
+pub(crate) async fn publish_message(
+    mut ws_message: WsMessageContent,
+    flagged_by: &[i32],
+    state: &AppState,
+) -> Result<WsMessage, WsError> {
+    let (author_id, room) = (ws_message.author.id, ws_message.room.clone());
+    // The message is delivered to the users connected to the room at the time
+    // it is sent.
+    let (recipients, tx) = match state.txs.lock().await.get_mut(&room) {
+        Some(ws_room) => {
+            ws_room.stop_typing(author_id);
+            (ws_room.other_members(author_id), Some(ws_room.tx.clone()))
+        }
+        None => (vec![], None),
+    };
+    ws_message.timestamp = Utc::now();
+    if ws_message.uuid.is_nil() {
+        ws_message.uuid = Uuid::new_v4();
+    }
+    ws_message.reception_status = match recipients.is_empty() {
+        true => WsReceptionStatus::Sent,
+        false => WsReceptionStatus::Delivered,
+    };
+    ws_message.receipts = vec![];
+    ws_message.reply_preview = None;
+    let attachment_ids: Vec<Uuid> = ws_message
+        .attachments
+        .drain(..)
+        .map(|attachment| attachment.id)
+        .collect();
+    if attachment_ids.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+        return Err(WsError::TooManyAttachments(ws_message.uuid));
+    }
+    if !attachment_ids.is_empty() {
+        match Attachment::find_not_linkable(
+            ws_message.uuid,
+            &attachment_ids,
+            author_id,
+            &state.pg_pool,
+        )
+        .await
+        {
+            Ok(unknown) if unknown.is_empty() => {}
+            Ok(_) => return Err(WsError::UnknownAttachments(ws_message.uuid)),
+            Err(e) => {
+                tracing::error!("An error happened while checking the attachments : {:?}", e);
+                return Err(WsError::MessageNotPersisted(ws_message.uuid));
+            }
+        }
+    }
+    if let Some(parent) = ws_message.reply_to {
+        match MessagePreview::find_in_room(parent, &room, &state.pg_pool).await {
+            Ok(Some(preview)) => ws_message.reply_preview = Some(preview),
+            Ok(None) => return Err(WsError::ReplyParentNotFound(parent)),
+            Err(e) => {
+                tracing::error!(
+                    "An error happened while retrieving the parent message : {:?}",
+                    e
+                );
+                return Err(WsError::MessageNotPersisted(ws_message.uuid));
+            }
+        }
+    }
+    match ws_message.persist(&state.pg_pool).await {
+        Ok(PersistOutcome::Created) => {}
+        // The client didn't receive the acknowledgment and sent the message
+        // again.
+        Ok(PersistOutcome::AlreadyPersisted(server_timestamp)) => {
+            return Ok(WsMessage::Ack {
+                uuid: ws_message.uuid,
+                server_timestamp,
+            });
+        }
+        Ok(PersistOutcome::Conflict) => {
+            tracing::warn!(
+                "The user {} tried to reuse the identifier {} of another user's message",
+                author_id,
+                ws_message.uuid
+            );
+            return Err(WsError::MessageNotPersisted(ws_message.uuid));
+        }
+        Err(e) => {
+            tracing::error!("An error happened while saving a message : {:?}", e);
+            return Err(WsError::MessageNotPersisted(ws_message.uuid));
+        }
+    }
+    flag_message(ws_message.uuid, flagged_by, &state.pg_pool).await;
+    if !attachment_ids.is_empty() {
+        match Attachment::link_to_message(
+            ws_message.uuid,
+            &attachment_ids,
+            author_id,
+            &state.pg_pool,
+        )
+        .await
+        {
+            Ok(attachments) => ws_message.attachments = attachments,
+            Err(e) => tracing::error!("An error happened while linking the attachments : {:?}", e),
+        }
+    }
+    if !recipients.is_empty() {
+        match MessageReceipt::mark_as_delivered(ws_message.uuid, &recipients, &state.pg_pool).await
+        {
+            Ok(receipts) => ws_message.receipts = receipts,
+            Err(e) => tracing::error!("An error happened while saving the receipts : {:?}", e),
+        }
+    }
+    let logins = mentioned_logins(&ws_message.content);
+    // The users mentioned within a direct conversation can't read it, so they
+    // aren't notified.
+    if !logins.is_empty() && dm_participants(&room).is_none() {
+        match persist_mentions(ws_message.uuid, &logins, &state.pg_pool).await {
+            Ok(mentioned) => {
+                let users = state.users.lock().await;
+                let msg = WsMessage::Mentioned(ws_message.clone());
+                for user_id in mentioned {
+                    users.send(user_id, &msg);
+                }
+            }
+            Err(e) => tracing::error!("An error happened while saving the mentions : {:?}", e),
+        }
+    }
+    notify_unread(&room, author_id, state).await;
+    let ack = WsMessage::Ack {
+        uuid: ws_message.uuid,
+        server_timestamp: ws_message.timestamp,
+    };
+    if let Some(tx) = tx {
+        let _ = tx.send(EncodedMessage::new(&WsMessage::Receive(ws_message)));
+    }
+    Ok(ack)
+}
+
+/// Informs the users following a room, apart from the author, that a new
+/// message is waiting to be read.
+///
+/// # Arguments
+///
+/// - room : The room the message has been sent in.
+/// - author_id : The author of the message.
+/// - state : The data shared across threads.
+async fn notify_unread(room: &str, author_id: i32, state: &AppState) {
+    let mut followers = match ReadMarker::find_followers(room, author_id, &state.pg_pool).await {
+        Ok(followers) => followers,
+        Err(e) => {
+            tracing::error!("An error happened while retrieving the followers : {:?}", e);
+            return;
+        }
+    };
+    // The participants of a direct conversation follow it even before they
+    // open it.
+    if let Some((first, second)) = dm_participants(room) {
+        followers.extend([first, second].into_iter().filter(|id| *id != author_id));
+        followers.sort_unstable();
+        followers.dedup();
+    }
+    let users = state.users.lock().await;
+    let msg = WsMessage::UnreadChanged {
+        room: room.to_string(),
+        delta: 1,
+    };
+    for user_id in followers {
+        users.send(user_id, &msg);
+    }
+}
+
+/// Exchanges the `Hello` messages opening a connection.
+///
+/// Returns the capabilities negotiated with the client, or none if the
+/// connection has to be dropped. The clients that don't open the connection
+/// with a compatible `Hello` are sent a close frame with the
+/// `WS_CLOSE_INCOMPATIBLE_PROTOCOL` code.
+///
+/// # Arguments
+///
+/// - socket : The struct used to communicate between the client and the server.
+async fn handshake(socket: &mut WebSocket) -> Option<Vec<Capability>> {
+    let text = match tokio::time::timeout(HANDSHAKE_TIMEOUT, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(text)))) => text,
+        Ok(Some(Ok(_))) => String::new(),
+        _ => return None,
+    };
+    // The clients predating the handshake open the connection with another
+    // message, and are considered as using the version 0.
+    let (protocol_version, capabilities) = match serde_json::from_str(&text) {
+        Ok(WsMessage::Hello {
+            protocol_version,
+            capabilities,
+        }) => (protocol_version, capabilities),
+        _ => (0, vec![]),
+    };
+    if !is_compatible(protocol_version) {
+        tracing::info!(
+            "Closing a connection using the incompatible protocol version {}",
+            protocol_version
+        );
+        let _ = socket
+            .send(Message::Close(Some(CloseFrame {
+                code: WS_CLOSE_INCOMPATIBLE_PROTOCOL,
+                reason: format!("The server only supports the protocol version {PROTOCOL_VERSION}")
+                    .into(),
+            })))
+            .await;
+        return None;
+    }
+    let capabilities = negotiate(&capabilities);
+    // The answer is always sent as JSON, the codec only applying once the
+    // client knows the capabilities negotiated.
+    let hello = WsMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        capabilities: capabilities.clone(),
+    };
+    socket
+        .send(Message::Text(serde_json::to_string(&hello).unwrap()))
+        .await
+        .ok()?;
+    Some(capabilities)
+}
+
+/// Builds the frame carrying a message to a client.
+///
+/// # Arguments
+///
+/// - codec : The codec negotiated with the client.
+/// - msg : The message, already encoded with every codec.
+fn frame(codec: WsCodec, msg: &EncodedMessage) -> Message {
+    match codec {
+        WsCodec::Json => Message::Text(msg.json().to_string()),
+        WsCodec::MessagePack => Message::Binary(msg.msgpack().to_vec()),
+    }
 }
 
 /// The socket handler
@@ -84,73 +796,374 @@ pub async fn ws_handler(
 /// - state : The data shared across threads.
 /// - room : The room name.
 /// - user : The connected user's infos.
-async fn handle_socket(socket: WebSocket, state: AppState, room: String) {
+async fn handle_socket(mut socket: WebSocket, state: AppState, room: String, user: PartialUser) {
+    let Some(capabilities) = handshake(&mut socket).await else {
+        return;
+    };
+    let codec = WsCodec::negotiated(&capabilities);
     let (mut sender, mut receiver) = socket.split();
-    let tx = {
+    let (tx, mut rx) = {
         let mut rooms = state.txs.lock().await;
-        match rooms.get(&room) {
-            Some(v) => v.clone(),
-            None => {
-                let (tx, _rx) = broadcast::channel(1000);
-                rooms.insert(room.clone(), tx.clone());
-                tx
-            }
-        }
+        let ws_room = rooms.entry(room.clone()).or_insert_with(WsRoom::default);
+        // The receiver is created first so that the user is informed of its
+        // own arrival along with the other members.
+        let rx = ws_room.tx.subscribe();
+        ws_room.join(&user);
+        (ws_room.tx.clone(), rx)
     };
-    let mut rx = tx.subscribe();
+    // Used to answer the client bound to this socket only.
+    let (direct_tx, mut direct_rx) = mpsc::channel::<EncodedMessage>(100);
+    state
+        .users
+        .lock()
+        .await
+        .register(user.id, direct_tx.clone(), capabilities);
 
     let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
+        loop {
+            let msg = tokio::select! {
+                msg = rx.recv() => match msg {
+                    Ok(msg) => msg,
+                    Err(_) => break,
+                },
+                Some(msg) = direct_rx.recv() => msg,
+            };
             // In any websocket error, break loop.
-            if sender.send(Message::Text(msg)).await.is_err() {
+            if sender.send(frame(codec, &msg)).await.is_err() {
                 break;
             }
         }
     });
 
     // This task will receive messages from client and send them to broadcast subscribers.
+    // Kept to clean up the room once the connection ends.
+    let (txs, room_name, user_id) = (state.txs.clone(), room.clone(), user.id);
+    let (users, user_tx) = (state.users.clone(), direct_tx.clone());
     let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(Message::Text(text))) = receiver.next().await {
-            if let Ok(msg) = serde_json::from_str(text.as_str()) {
+        let mut last_typing: Option<Instant> = None;
+        let rate_limit = RateLimit::for_profile(user.profile);
+        while let Some(Ok(received)) = receiver.next().await {
+            // The clients may send either JSON or MessagePack frames.
+            let decoded = match received {
+                Message::Text(text) => serde_json::from_str(&text).ok(),
+                Message::Binary(bytes) => from_msgpack(&bytes).ok(),
+                _ => break,
+            };
+            if let Some(msg) = decoded {
                 match msg {
                     WsMessage::Close => break,
-                    WsMessage::Ping => {
-                        let _ = tx.send(serde_json::to_string(&WsMessage::Pong).unwrap());
-                    }
+                    WsMessage::Ping => reply(&direct_tx, &WsMessage::Pong).await,
                     WsMessage::Pong | WsMessage::ClientKeepAlive => continue,
+                    WsMessage::Typing => {
+                        if matches!(last_typing, Some(last) if last.elapsed() < TYPING_THROTTLE) {
+                            continue;
+                        }
+                        last_typing = Some(Instant::now());
+                        if let Some(ws_room) = state.txs.lock().await.get_mut(&room) {
+                            ws_room.start_typing(&user);
+                        }
+                    }
+                    WsMessage::StoppedTyping => {
+                        last_typing = None;
+                        if let Some(ws_room) = state.txs.lock().await.get_mut(&room) {
+                            ws_room.stop_typing(user.id);
+                        }
+                    }
                     WsMessage::Send(mut ws_message) => {
-                        ws_message.reception_status = WsReceptionStatus::Sent;
-                        if let Err(e) = ws_message.persist(&state.pg_pool).await {
-                            tracing::error!("An error happened while saving a message : {:?}", e);
-                        } else {
-                            let _ = tx.send(
-                                serde_json::to_string(&WsMessage::Receive(ws_message)).unwrap(),
-                            );
+                        last_typing = None;
+                        ws_message.room = room.clone();
+                        if let Err(e) = ws_message.validate() {
+                            reply(
+                                &direct_tx,
+                                &WsMessage::Error(WsError::InvalidMessage(
+                                    ws_message.uuid,
+                                    e.into(),
+                                )),
+                            )
+                            .await;
+                            continue;
+                        }
+                        if let Some(retry_after) = throttle(&rate_limit, user.id, &state.redis_pool)
+                        {
+                            reply(
+                                &direct_tx,
+                                &WsMessage::SlowDown {
+                                    uuid: ws_message.uuid,
+                                    retry_after_ms: retry_after.as_millis() as u64,
+                                },
+                            )
+                            .await;
+                            continue;
+                        }
+                        let flagged_by = match filter_content(
+                            ws_message.uuid,
+                            &ws_message.content,
+                            &state.filter_manager,
+                        )
+                        .await
+                        {
+                            Ok((content, flagged_by)) => {
+                                ws_message.content = content;
+                                flagged_by
+                            }
+                            Err(error) => {
+                                reply(&direct_tx, &WsMessage::Error(error)).await;
+                                continue;
+                            }
+                        };
+                        // The message's metadata is set by the server, only the
+                        // content and the identifier are kept from the client.
+                        ws_message.author = user.clone();
+                        match publish_message(ws_message, &flagged_by, &state).await {
+                            Ok(ack) => reply(&direct_tx, &ack).await,
+                            Err(error) => reply(&direct_tx, &WsMessage::Error(error)).await,
+                        }
+                    }
+                    WsMessage::Edit { uuid, content } => {
+                        let edited = WsMessageContent {
+                            uuid,
+                            content: content.clone(),
+                            room: room.clone(),
+                            ..WsMessageContent::default()
+                        };
+                        if let Err(e) = edited.validate() {
+                            reply(
+                                &direct_tx,
+                                &WsMessage::Error(WsError::InvalidMessage(uuid, e.into())),
+                            )
+                            .await;
+                            continue;
+                        }
+                        let (content, flagged_by) =
+                            match filter_content(uuid, &content, &state.filter_manager).await {
+                                Ok(filtered) => filtered,
+                                Err(error) => {
+                                    reply(&direct_tx, &WsMessage::Error(error)).await;
+                                    continue;
+                                }
+                            };
+                        match WsMessageContent::edit(uuid, &content, user.id, &room, &state.pg_pool)
+                            .await
+                        {
+                            Ok(Some(edited_at)) => {
+                                flag_message(uuid, &flagged_by, &state.pg_pool).await;
+                                let _ = tx.send(EncodedMessage::new(&WsMessage::Edited {
+                                    uuid,
+                                    content,
+                                    edited_at,
+                                }));
+                            }
+                            Ok(None) => {
+                                reply(
+                                    &direct_tx,
+                                    &WsMessage::Error(WsError::MessageNotEditable(uuid)),
+                                )
+                                .await;
+                            }
+                            Err(e) => {
+                                tracing::error!(
+                                    "An error happened while editing a message : {:?}",
+                                    e
+                                );
+                            }
+                        }
+                    }
+                    WsMessage::Delete(messages) => {
+                        match WsMessageContent::soft_delete(
+                            &messages,
+                            Some(&user),
+                            Some(&room),
+                            &state.pg_pool,
+                        )
+                        .await
+                        {
+                            Ok(deleted) => {
+                                let not_deleted: Vec<_> = messages
+                                    .into_iter()
+                                    .filter(|uuid| !deleted.contains(uuid))
+                                    .collect();
+                                if !not_deleted.is_empty() {
+                                    reply(
+                                        &direct_tx,
+                                        &WsMessage::Error(WsError::MessagesNotDeletable(
+                                            not_deleted,
+                                        )),
+                                    )
+                                    .await;
+                                }
+                            }
+                            Err(e) => {
+                                tracing::error!(
+                                    "An error happened while deleting the messages : {:?}",
+                                    e
+                                );
+                            }
+                        }
+                    }
+                    WsMessage::React { uuid, emoji } => {
+                        if !MessageReaction::is_valid_emoji(&emoji) {
+                            reply(
+                                &direct_tx,
+                                &WsMessage::Error(WsError::InvalidReaction(emoji)),
+                            )
+                            .await;
+                            continue;
+                        }
+                        match MessageReaction::add(uuid, user.id, &emoji, &room, &state.pg_pool)
+                            .await
+                        {
+                            Ok(true) => broadcast_reactions(uuid, &tx, &state.pg_pool).await,
+                            Ok(false) => {}
+                            Err(e) => {
+                                tracing::error!("An error happened while reacting : {:?}", e)
+                            }
+                        }
+                    }
+                    WsMessage::Unreact { uuid, emoji } => {
+                        match MessageReaction::remove(uuid, user.id, &emoji, &room, &state.pg_pool)
+                            .await
+                        {
+                            Ok(true) => broadcast_reactions(uuid, &tx, &state.pg_pool).await,
+                            Ok(false) => {}
+                            Err(e) => tracing::error!(
+                                "An error happened while removing a reaction : {:?}",
+                                e
+                            ),
+                        }
+                    }
+                    WsMessage::Pin(uuid) => {
+                        if !can_pin(&room, &user) {
+                            reply(&direct_tx, &WsMessage::Error(WsError::PinNotAllowed(uuid)))
+                                .await;
+                            continue;
+                        }
+                        match PinnedMessage::pin(uuid, user.id, &room, &state.pg_pool).await {
+                            Ok(PinOutcome::Pinned) => {
+                                broadcast_pins(&room, &tx, &state.pg_pool).await
+                            }
+                            Ok(PinOutcome::AlreadyPinned) => {}
+                            Ok(PinOutcome::NotFound) => {
+                                reply(
+                                    &direct_tx,
+                                    &WsMessage::Error(WsError::PinnedMessageNotFound(uuid)),
+                                )
+                                .await;
+                            }
+                            Err(e) => {
+                                tracing::error!("An error happened while pinning : {:?}", e)
+                            }
+                        }
+                    }
+                    WsMessage::Unpin(uuid) => {
+                        if !can_pin(&room, &user) {
+                            reply(&direct_tx, &WsMessage::Error(WsError::PinNotAllowed(uuid)))
+                                .await;
+                            continue;
+                        }
+                        match PinnedMessage::unpin(uuid, &room, &state.pg_pool).await {
+                            Ok(true) => broadcast_pins(&room, &tx, &state.pg_pool).await,
+                            Ok(false) => {}
+                            Err(e) => {
+                                tracing::error!("An error happened while unpinning : {:?}", e)
+                            }
                         }
                     }
                     WsMessage::RetrieveMessages(session_id) => {
                         let messages: Vec<WsMessageContent> =
                             WsMessageContent::query_all_for_room(&room, &state.pg_pool).await;
-                        let _ = tx.send(
-                            serde_json::to_string(&WsMessage::MessagesRetrieved {
+                        reply(
+                            &direct_tx,
+                            &WsMessage::MessagesRetrieved {
                                 messages,
                                 session_id,
-                            })
-                            .unwrap(),
-                        );
+                            },
+                        )
+                        .await;
+                    }
+                    WsMessage::RetrieveMessagesPage {
+                        session_id,
+                        before,
+                        page_size,
+                    } => {
+                        match WsMessageContent::query_page_for_room(
+                            &room,
+                            before,
+                            page_size,
+                            &state.pg_pool,
+                        )
+                        .await
+                        {
+                            Ok(page) => {
+                                reply(
+                                    &direct_tx,
+                                    &WsMessage::MessagesPageRetrieved { page, session_id },
+                                )
+                                .await;
+                            }
+                            Err(e) => tracing::error!(
+                                "An error happened while retrieving the messages : {:?}",
+                                e
+                            ),
+                        }
                     }
                     WsMessage::Seen(messages) => {
-                        if let Err(e) =
-                            WsMessageContent::mark_as_seen(&messages, &state.pg_pool).await
+                        match WsMessageContent::find_not_in_room(&messages, &room, &state.pg_pool)
+                            .await
+                        {
+                            Ok(not_visible) if !not_visible.is_empty() => {
+                                reply(
+                                    &direct_tx,
+                                    &WsMessage::Error(WsError::MessagesNotVisible(not_visible)),
+                                )
+                                .await;
+                                continue;
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                tracing::error!(
+                                    "An error happened while checking the messsages : {:?}.",
+                                    e
+                                );
+                                continue;
+                            }
+                        }
+                        match WsMessageContent::mark_as_seen(
+                            &messages,
+                            user.id,
+                            &room,
+                            &state.pg_pool,
+                        )
+                        .await
                         {
-                            tracing::error!(
+                            Ok(seen) if !seen.is_empty() => {
+                                let _ = tx.send(EncodedMessage::new(&WsMessage::MessagesSeen {
+                                    messages: seen,
+                                    user_id: user.id,
+                                    name: user.name.clone(),
+                                    seen_at: Utc::now(),
+                                }));
+                            }
+                            Ok(_) => {}
+                            Err(e) => tracing::error!(
                                 "An error happened while updating the messsages : {:?}.",
                                 e
-                            );
-                        } else {
-                            let _ = tx.send(
-                                serde_json::to_string(&WsMessage::MessagesSeen(messages)).unwrap(),
-                            );
+                            ),
+                        }
+                        match ReadMarker::advance(user.id, &room, &messages, &state.pg_pool).await {
+                            Ok(read) if read > 0 => {
+                                state.users.lock().await.send(
+                                    user.id,
+                                    &WsMessage::UnreadChanged {
+                                        room: room.clone(),
+                                        delta: -read,
+                                    },
+                                );
+                            }
+                            Ok(_) => {}
+                            Err(e) => tracing::error!(
+                                "An error happened while moving the read marker : {:?}.",
+                                e
+                            ),
                         }
                     }
                     _ => {}
@@ -163,4 +1176,11 @@ async fn handle_socket(socket: WebSocket, state: AppState, room: String) {
         _ = (&mut send_task) => recv_task.abort(),
         _ = (&mut recv_task) => send_task.abort(),
     };
+
+    users.lock().await.unregister(user_id, &user_tx);
+    let mut rooms = txs.lock().await;
+    if let Some(ws_room) = rooms.get_mut(&room_name) {
+        ws_room.stop_typing(user_id);
+        ws_room.leave(user_id);
+    }
 }
@@ -1,5 +1,6 @@
 use sqlx::types::Uuid;
-use tchatchers_core::ws_message::WsMessageContent;
+use tchatchers_core::ws_message::{MessageSearchQuery, WsMessageContent};
+use validator::Validate;
 
 use crate::errors::CliError;
 
@@ -8,6 +9,9 @@ pub struct MessageAction;
 impl MessageAction {
     /// Deletes messages with the given UUIDs from the database.
     ///
+    /// The messages are kept as tombstones, and the connected clients are
+    /// notified of the deletion.
+    ///
     /// # Arguments
     ///
     /// * `messages_uuid` - A vector of UUIDs representing the messages to delete.
@@ -18,7 +22,31 @@ impl MessageAction {
     /// returns an error of type `CliError`.
     pub async fn delete_messages(messages_uuid: Vec<Uuid>) -> Result<(), CliError> {
         let pool = tchatchers_core::pool::get_pg_pool().await;
-        WsMessageContent::delete_messages(&messages_uuid, &pool).await?;
+        let deleted = WsMessageContent::soft_delete(&messages_uuid, None, None, &pool).await?;
+        println!("{} messages deleted", deleted.len());
+        Ok(())
+    }
+
+    /// Searches the messages matching the query in every room and prints them.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The search criteria.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), CliError>` - Returns `Ok(())` if the operation was successful, otherwise an error of type `CliError`.
+    pub async fn search_messages(query: MessageSearchQuery) -> Result<(), CliError> {
+        query.validate()?;
+        let pool = tchatchers_core::pool::get_pg_pool().await;
+        let messages = WsMessageContent::search(&query, None, &pool).await?;
+        println!("{} messages found", messages.len());
+        messages.iter().for_each(|m| {
+            println!(
+                "{} [{}] {} : {} ({})",
+                m.timestamp, m.room, m.author.login, m.content, m.uuid
+            )
+        });
         Ok(())
     }
 }
@@ -1,5 +1,8 @@
 use crate::errors::CliError;
+use tchatchers_core::retention::RetentionPolicy;
+use tchatchers_core::room::RoomNameValidator;
 use tchatchers_core::ws_message::{WsMessageContent, WsMessageStats};
+use validator::Validate;
 
 /// Struct for performing actions related to chat rooms.
 pub struct RoomAction;
@@ -89,4 +92,110 @@ impl RoomAction {
             .for_each(|(i, m)| println!("- #{} : {m:#?}\n", i + 1));
         Ok(())
     }
+
+    /// Asynchronously sets the retention policy of a chat room, or the default one.
+    ///
+    /// A policy without any limit keeps the messages of the room forever.
+    ///
+    /// # Arguments
+    ///
+    /// * `room_name` - The name of the chat room, none for the default policy.
+    /// * `max_age_days` - The number of days after which the messages are purged.
+    /// * `max_messages` - The number of most recent messages kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CliError` if the room name is invalid or the operation fails.
+    pub async fn set_retention(
+        room_name: Option<String>,
+        max_age_days: Option<i32>,
+        max_messages: Option<i32>,
+    ) -> Result<(), CliError> {
+        if let Some(room_name) = &room_name {
+            RoomNameValidator::from(room_name.clone()).validate()?;
+        }
+        let pool = tchatchers_core::pool::get_pg_pool().await;
+        let policy = RetentionPolicy {
+            room: room_name,
+            max_age_days,
+            max_messages,
+        };
+        policy.upsert(&pool).await?;
+        println!("Retention policy set : {policy:#?}");
+        Ok(())
+    }
+
+    /// Asynchronously removes the retention policy of a chat room, or the default one.
+    ///
+    /// # Arguments
+    ///
+    /// * `room_name` - The name of the chat room, none for the default policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CliError` if the operation fails.
+    pub async fn unset_retention(room_name: Option<String>) -> Result<(), CliError> {
+        let pool = tchatchers_core::pool::get_pg_pool().await;
+        let result = RetentionPolicy::delete(room_name.as_deref(), &pool).await?;
+        match (result.rows_affected(), room_name) {
+            (0, _) => println!("No retention policy to remove"),
+            (_, Some(room_name)) => println!("Retention policy of room '{room_name}' removed"),
+            (_, None) => println!("Default retention policy removed"),
+        }
+        Ok(())
+    }
+
+    /// Asynchronously prints the retention policy applied to a chat room, or all the policies if no room is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `room_name` - The name of the chat room.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CliError` if the operation fails.
+    pub async fn show_retention(room_name: Option<String>) -> Result<(), CliError> {
+        let pool = tchatchers_core::pool::get_pg_pool().await;
+        match room_name {
+            Some(room_name) => match RetentionPolicy::find_for_room(&room_name, &pool).await? {
+                Some(policy) if policy.room.is_none() => {
+                    println!("Room '{room_name}' falls back on the default policy : {policy:#?}")
+                }
+                Some(policy) => println!("Policy of room '{room_name}' : {policy:#?}"),
+                None => println!("The messages of room '{room_name}' are kept forever"),
+            },
+            None => {
+                let policies = RetentionPolicy::find_all(&pool).await?;
+                println!("Retention policies\n---\n");
+                policies.iter().for_each(|p| println!("- {p:#?}\n"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Asynchronously prints the number of messages each retention policy would purge, without removing any.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CliError` if the operation fails.
+    pub async fn retention_dry_run() -> Result<(), CliError> {
+        let pool = tchatchers_core::pool::get_pg_pool().await;
+        let reports = RetentionPolicy::dry_run(&pool).await?;
+        println!("Retention dry run\n---\n");
+        for report in &reports {
+            let policy = match report.is_default {
+                true => "default policy",
+                false => "room policy",
+            };
+            println!(
+                "- {} : {} messages would be purged ({policy})",
+                report.room, report.expired_messages
+            );
+        }
+        println!(
+            "\n{} messages would be purged in total",
+            reports.iter().map(|r| r.expired_messages).sum::<i64>()
+        );
+        Ok(())
+    }
 }
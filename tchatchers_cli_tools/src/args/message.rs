@@ -1,3 +1,4 @@
+use sqlx::types::chrono::{DateTime, Utc};
 use sqlx::types::Uuid;
 
 /// The actions that can be run on the messages stored in the database.
@@ -9,4 +10,22 @@ pub enum MessageArgAction {
         /// UUIDs of the messages to delete.
         messages_uuid: Vec<Uuid>,
     },
+    /// Search the messages matching the given text.
+    #[command(about = "Search the messages matching the given text")]
+    Search {
+        /// The text searched.
+        query: String,
+        /// The room the messages have to belong to.
+        #[arg(short, long)]
+        room: Option<String>,
+        /// The login of the author of the messages.
+        #[arg(short, long)]
+        author: Option<String>,
+        /// Only messages sent before this date are returned (RFC 3339).
+        #[arg(short, long)]
+        before: Option<DateTime<Utc>>,
+        /// Only messages sent after this date are returned (RFC 3339).
+        #[arg(long)]
+        after: Option<DateTime<Utc>>,
+    },
 }
@@ -10,4 +10,47 @@ pub enum RoomArgAction {
     /// Prints global activity of the application's rooms.
     #[command(about = "Print global activity of the application's rooms")]
     Activity,
+    /// Manages the retention policies of the rooms.
+    #[command(about = "Manages the retention policies of the rooms")]
+    Retention {
+        #[command(subcommand)]
+        action: RetentionArgAction,
+    },
+}
+
+/// The actions that can be performed on the retention policies, which limit how long the messages of a room are kept.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum RetentionArgAction {
+    /// Sets the retention policy of a room, or the default one.
+    #[command(about = "Sets the retention policy of a room, or the default one")]
+    Set {
+        /// The room the policy applies to.
+        #[arg(required_unless_present = "default")]
+        room_name: Option<String>,
+        /// Sets the default policy, applied to the rooms without a policy of their own.
+        #[arg(long, conflicts_with = "room_name")]
+        default: bool,
+        /// The number of days after which the messages are purged.
+        #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+        max_age_days: Option<i32>,
+        /// The number of most recent messages kept.
+        #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+        max_messages: Option<i32>,
+    },
+    /// Removes the retention policy of a room, or the default one.
+    #[command(about = "Removes the retention policy of a room, or the default one")]
+    Unset {
+        /// The room the policy applies to.
+        #[arg(required_unless_present = "default")]
+        room_name: Option<String>,
+        /// Removes the default policy.
+        #[arg(long, conflicts_with = "room_name")]
+        default: bool,
+    },
+    /// Prints the policy applied to a room, or all the policies if no room is given.
+    #[command(about = "Prints the policy applied to a room, or all the policies")]
+    Show { room_name: Option<String> },
+    /// Prints the number of messages each policy would purge, without removing any.
+    #[command(about = "Prints the number of messages each policy would purge")]
+    DryRun,
 }
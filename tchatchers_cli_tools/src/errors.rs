@@ -33,6 +33,8 @@ pub enum ErrorKind {
     IoError,
     /// Error linked with template generation.
     TemplateError,
+    /// The arguments given by the user are not valid.
+    ValidationError,
 }
 
 impl From<sqlx::Error> for CliError {
@@ -53,6 +55,12 @@ impl From<askama::Error> for CliError {
     }
 }
 
+impl From<validator::ValidationErrors> for CliError {
+    fn from(value: validator::ValidationErrors) -> Self {
+        Self::new(value.to_string(), ErrorKind::ValidationError)
+    }
+}
+
 impl std::process::Termination for CliError {
     fn report(self) -> std::process::ExitCode {
         eprintln!("The process ended with the following error");
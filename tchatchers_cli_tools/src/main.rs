@@ -15,9 +15,10 @@ mod errors;
 use std::process::{ExitCode, Termination};
 
 use actions::{env::EnvAction, message::MessageAction, room::RoomAction};
-use args::{message::MessageArgAction, CliArgs};
+use args::{message::MessageArgAction, room::RetentionArgAction, CliArgs};
 use clap::Parser;
 use errors::CliError;
+use tchatchers_core::ws_message::MessageSearchQuery;
 
 use crate::actions::user::UserAction;
 
@@ -76,6 +77,23 @@ async fn run_main() -> Result<(), CliError> {
                 info!("Deleting messages with UUIDs: {:?}", messages_uuid);
                 MessageAction::delete_messages(messages_uuid).await?
             }
+            MessageArgAction::Search {
+                query,
+                room,
+                author,
+                before,
+                after,
+            } => {
+                info!("Searching the messages matching: {}", query);
+                MessageAction::search_messages(MessageSearchQuery {
+                    q: query,
+                    room,
+                    author,
+                    before,
+                    after,
+                })
+                .await?
+            }
         },
         args::CliEntityArg::Env { action } => match action {
             args::env::EnvArgAction::Create => {
@@ -104,6 +122,29 @@ async fn run_main() -> Result<(), CliError> {
                 info!("Getting the activity...");
                 RoomAction::get_activity().await?
             }
+            args::room::RoomArgAction::Retention { action } => match action {
+                RetentionArgAction::Set {
+                    room_name,
+                    max_age_days,
+                    max_messages,
+                    ..
+                } => {
+                    info!("Setting the retention policy...");
+                    RoomAction::set_retention(room_name, max_age_days, max_messages).await?
+                }
+                RetentionArgAction::Unset { room_name, .. } => {
+                    info!("Removing the retention policy...");
+                    RoomAction::unset_retention(room_name).await?
+                }
+                RetentionArgAction::Show { room_name } => {
+                    info!("Getting the retention policies...");
+                    RoomAction::show_retention(room_name).await?
+                }
+                RetentionArgAction::DryRun => {
+                    info!("Computing the messages to purge...");
+                    RoomAction::retention_dry_run().await?
+                }
+            },
         },
     }
     Ok(())
@@ -0,0 +1,77 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Compares the size and the speed of the JSON and MessagePack encodings of
+//! the websocket messages.
+//!
+//! Run it with `cargo bench -p tchatchers_core --bench ws_codec`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tchatchers_core::codec::{from_msgpack, to_msgpack};
+use tchatchers_core::user::PartialUser;
+use tchatchers_core::ws_message::{WsMessage, WsMessageContent};
+use uuid::Uuid;
+
+/// The number of times each operation is repeated.
+const ITERATIONS: u32 = 1_000;
+
+/// Builds the message sent when a client retrieves the history of a room.
+fn retrieved_messages(count: usize) -> WsMessage {
+    let author = PartialUser {
+        id: 42,
+        login: "jdoe".into(),
+        name: "John Doe".into(),
+        pfp: Some("/static/pfp/jdoe.png".into()),
+        is_authorized: true,
+        ..PartialUser::default()
+    };
+    let messages = (0..count)
+        .map(|index| WsMessageContent {
+            uuid: Uuid::new_v4(),
+            content: format!("This is the message number {index} of the room"),
+            author: author.clone(),
+            room: "general".into(),
+            ..WsMessageContent::default()
+        })
+        .collect();
+    WsMessage::MessagesRetrieved {
+        messages,
+        session_id: Uuid::new_v4(),
+    }
+}
+
+/// Returns the mean duration of the operation.
+fn measure<T>(mut operation: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(operation());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    for count in [1, 10, 100] {
+        let msg = retrieved_messages(count);
+        let json = serde_json::to_string(&msg).unwrap();
+        let msgpack = to_msgpack(&msg);
+        println!("{count} message(s) retrieved");
+        println!(
+            "  size   : json {} bytes, msgpack {} bytes ({:.0}%)",
+            json.len(),
+            msgpack.len(),
+            100.0 * msgpack.len() as f64 / json.len() as f64
+        );
+        println!(
+            "  encode : json {:?}, msgpack {:?}",
+            measure(|| serde_json::to_string(&msg).unwrap()),
+            measure(|| to_msgpack(&msg))
+        );
+        println!(
+            "  decode : json {:?}, msgpack {:?}",
+            measure(|| serde_json::from_str::<WsMessage>(&json).unwrap()),
+            measure(|| from_msgpack(&msgpack).unwrap())
+        );
+    }
+}
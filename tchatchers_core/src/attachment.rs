@@ -0,0 +1,297 @@
+//! Attachments are the files joined to the messages.
+//!
+//! They are uploaded prior the message being sent, and linked to it once it
+//! is persisted. The attachments that never got linked to a message, or whose
+//! message has been deleted, are removed.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use uuid::Uuid;
+
+/// The maximum size of an attachment, in bytes.
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// The maximum number of attachments joined to a single message.
+pub const MAX_ATTACHMENTS_PER_MESSAGE: usize = 5;
+
+/// The path under which the attachments are downloaded.
+pub const ATTACHMENTS_PATH: &str = "/api/attachment";
+
+/// The MIME types accepted, along with the extension of the stored files.
+const ALLOWED_MIME_TYPES: [(&str, &str); 7] = [
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("application/pdf", "pdf"),
+    ("application/zip", "zip"),
+    ("text/plain", "txt"),
+];
+
+/// A file joined to a message.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    /// The attachment identifier.
+    pub id: Uuid,
+    /// The name of the file, as given by the uploader.
+    pub file_name: String,
+    /// The MIME type of the file, detected from its content.
+    pub mime_type: String,
+    /// The size of the file, in bytes.
+    pub size: i64,
+    /// The SHA-256 checksum of the file, hex encoded.
+    pub checksum: String,
+}
+
+impl Attachment {
+    /// Whether the attachment can be previewed as an image.
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+
+    /// The name of the stored file.
+    pub fn stored_file_name(&self) -> String {
+        let extension = ALLOWED_MIME_TYPES
+            .iter()
+            .find(|(mime_type, _)| *mime_type == self.mime_type)
+            .map(|(_, extension)| *extension)
+            .unwrap_or("bin");
+        format!("{}.{extension}", self.id)
+    }
+
+    /// The URL the attachment can be downloaded from, by the users allowed
+    /// to access the room of its message.
+    pub fn url(&self) -> String {
+        format!("{ATTACHMENTS_PATH}/{}", self.id)
+    }
+}
+
+/// Detects the MIME type of a file from its content.
+///
+/// Returns `None` if the file isn't of an accepted type, the type declared by
+/// the uploader is never trusted.
+///
+/// # Arguments
+///
+/// - content : The content of the file.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::attachment::detect_mime_type;
+///
+/// assert_eq!(detect_mime_type(b"\x89PNG\r\n\x1a\n..."), Some("image/png"));
+/// assert_eq!(detect_mime_type(b"%PDF-1.7"), Some("application/pdf"));
+/// assert_eq!(detect_mime_type("Hello, world!".as_bytes()), Some("text/plain"));
+/// assert_eq!(detect_mime_type(b"MZ\x90\x00\x03"), None);
+/// ```
+pub fn detect_mime_type(content: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 7] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"PK\x05\x06", "application/zip"),
+    ];
+    if let Some((_, mime_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| content.starts_with(signature))
+    {
+        return Some(mime_type);
+    }
+    if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    match std::str::from_utf8(content) {
+        Ok(text) if !text.contains('\0') => Some("text/plain"),
+        _ => None,
+    }
+}
+
+/// Builds the expression aggregating the attachments of a message as JSON.
+///
+/// # Arguments
+///
+/// - message_uuid : The expression giving the identifier of the message.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub(crate) fn aggregate_attachments(message_uuid: &str) -> String {
+    format!(
+        "COALESCE((SELECT json_agg(json_build_object('id', a.id, 'fileName', a.file_name, 'mimeType', a.mime_type, 'size', a.size, 'checksum', a.checksum) ORDER BY a.created_at)
+        FROM ATTACHMENT a WHERE a.message_uuid = {message_uuid}), '[]'::JSON)"
+    )
+}
+
+/// The column containing the aggregated attachments of a message.
+#[cfg(any(feature = "back", feature = "cli"))]
+#[derive(sqlx::FromRow)]
+pub struct AttachmentsRow {
+    attachments: sqlx::types::Json<Vec<Attachment>>,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl From<AttachmentsRow> for Vec<Attachment> {
+    fn from(row: AttachmentsRow) -> Self {
+        row.attachments.0
+    }
+}
+
+/// An attachment along with the room of the message it is joined to.
+#[cfg(any(feature = "back", feature = "cli"))]
+#[derive(sqlx::FromRow)]
+struct AttachmentWithRoom {
+    #[sqlx(flatten)]
+    attachment: Attachment,
+    room: String,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl Attachment {
+    /// Records an uploaded attachment, not linked to any message yet.
+    ///
+    /// # Arguments
+    ///
+    /// - uploaded_by : The user who uploaded the file.
+    /// - pool : The connection pool.
+    pub async fn insert(
+        &self,
+        uploaded_by: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+        sqlx::query("INSERT INTO ATTACHMENT(id, file_name, mime_type, size, checksum, uploaded_by) VALUES ($1,$2,$3,$4,$5,$6)")
+            .bind(self.id)
+            .bind(&self.file_name)
+            .bind(&self.mime_type)
+            .bind(self.size)
+            .bind(&self.checksum)
+            .bind(uploaded_by)
+            .execute(pool)
+            .await
+    }
+
+    /// Links the attachments uploaded by the user to a message.
+    ///
+    /// Returns the attachments linked, the ones already linked or uploaded by
+    /// another user being ignored.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message the attachments are joined to.
+    /// - ids : The attachments to link.
+    /// - uploaded_by : The author of the message.
+    /// - pool : The connection pool.
+    pub async fn link_to_message(
+        message_uuid: Uuid,
+        ids: &[Uuid],
+        uploaded_by: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "UPDATE ATTACHMENT SET message_uuid=$1
+            WHERE id = ANY($2) AND uploaded_by=$3 AND message_uuid IS NULL
+            RETURNING *",
+        )
+        .bind(message_uuid)
+        .bind(ids)
+        .bind(uploaded_by)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns, among the given attachments, the ones that can't be joined to
+    /// the message, because they don't exist, have been uploaded by another
+    /// user or are already joined to another message.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message the attachments are joined to.
+    /// - ids : The attachments to check.
+    /// - uploaded_by : The author of the message.
+    /// - pool : The connection pool.
+    pub async fn find_not_linkable(
+        message_uuid: Uuid,
+        ids: &[Uuid],
+        uploaded_by: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT u.id FROM UNNEST($1::UUID[]) AS u(id)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM ATTACHMENT a WHERE a.id = u.id AND a.uploaded_by = $2
+                AND (a.message_uuid IS NULL OR a.message_uuid = $3)
+            )",
+        )
+        .bind(ids)
+        .bind(uploaded_by)
+        .bind(message_uuid)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns an attachment along with the room of the message it is joined
+    /// to.
+    ///
+    /// The attachments not joined to a message, or whose message has been
+    /// deleted, aren't returned.
+    ///
+    /// # Arguments
+    ///
+    /// - id : The attachment identifier.
+    /// - pool : The connection pool.
+    pub async fn find_with_room(
+        id: Uuid,
+        pool: &sqlx::PgPool,
+    ) -> Result<Option<(Self, String)>, sqlx::Error> {
+        let row: Option<AttachmentWithRoom> = sqlx::query_as(
+            "SELECT a.id, a.file_name, a.mime_type, a.size, a.checksum, m.room FROM ATTACHMENT a
+            INNER JOIN MESSAGE m ON a.message_uuid = m.uuid
+            WHERE a.id = $1 AND m.deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(|row| (row.attachment, row.room)))
+    }
+
+    /// Removes the attachments that haven't been linked to any message since
+    /// they were uploaded.
+    ///
+    /// Returns the attachments removed.
+    ///
+    /// # Arguments
+    ///
+    /// - uploaded_before : The attachments uploaded after this moment are kept.
+    /// - pool : The connection pool.
+    pub async fn delete_unlinked(
+        uploaded_before: chrono::DateTime<chrono::Utc>,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "DELETE FROM ATTACHMENT WHERE message_uuid IS NULL AND created_at < $1 RETURNING *",
+        )
+        .bind(uploaded_before)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns, among the given ids, the ones of the attachments still
+    /// recorded.
+    ///
+    /// # Arguments
+    ///
+    /// - ids : The identifiers to look up.
+    /// - pool : The connection pool.
+    pub async fn find_existing_ids(
+        ids: &[Uuid],
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar("SELECT id FROM ATTACHMENT WHERE id = ANY($1)")
+            .bind(ids)
+            .fetch_all(pool)
+            .await
+    }
+}
@@ -0,0 +1,140 @@
+//! The codecs used to encode the messages exchanged over the websocket.
+//!
+//! The messages are encoded in JSON by default. The clients negotiating the
+//! MessagePack capability are sent binary frames instead, which are more
+//! compact and faster to process, the JSON frames remaining accepted in both
+//! directions.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use once_cell::sync::OnceCell;
+use std::sync::Arc;
+
+use crate::protocol::Capability;
+use crate::ws_message::WsMessage;
+
+/// The encoding of the frames sent to a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WsCodec {
+    /// Text frames holding JSON.
+    #[default]
+    Json,
+    /// Binary frames holding MessagePack.
+    MessagePack,
+}
+
+impl WsCodec {
+    /// Returns the codec to use given the capabilities negotiated with the
+    /// client.
+    ///
+    /// # Arguments
+    ///
+    /// - capabilities : The capabilities negotiated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tchatchers_core::codec::WsCodec;
+    /// use tchatchers_core::protocol::Capability;
+    ///
+    /// assert_eq!(WsCodec::negotiated(&[Capability::MessagePack]), WsCodec::MessagePack);
+    /// assert_eq!(WsCodec::negotiated(&[Capability::Mentions]), WsCodec::Json);
+    /// ```
+    pub fn negotiated(capabilities: &[Capability]) -> Self {
+        match capabilities.contains(&Capability::MessagePack) {
+            true => Self::MessagePack,
+            false => Self::Json,
+        }
+    }
+}
+
+/// Encodes a message as MessagePack.
+///
+/// The structures are encoded as maps rather than arrays, so that the fields
+/// that are skipped or defaulted keep their meaning.
+///
+/// # Arguments
+///
+/// - msg : The message to encode.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::codec::{from_msgpack, to_msgpack};
+/// use tchatchers_core::ws_message::WsMessage;
+///
+/// let msg = WsMessage::UnreadChanged { room: "general".into(), delta: -3 };
+/// let encoded = to_msgpack(&msg);
+/// assert!(encoded.len() < serde_json::to_string(&msg).unwrap().len());
+/// assert_eq!(from_msgpack(&encoded).unwrap(), msg);
+/// ```
+pub fn to_msgpack(msg: &WsMessage) -> Vec<u8> {
+    rmp_serde::to_vec_named(msg).expect("The messages can always be encoded")
+}
+
+/// Decodes a message encoded as MessagePack.
+///
+/// # Arguments
+///
+/// - bytes : The encoded message.
+pub fn from_msgpack(bytes: &[u8]) -> Result<WsMessage, rmp_serde::decode::Error> {
+    rmp_serde::from_slice(bytes)
+}
+
+/// A message shared between connections, encoded at most once with each
+/// codec.
+///
+/// The message is only encoded with a codec the first time a connection
+/// using it requires it, the clones sharing the encoded values.
+#[derive(Debug, Clone)]
+pub struct EncodedMessage(Arc<LazyEncodings>);
+
+#[derive(Debug)]
+struct LazyEncodings {
+    msg: WsMessage,
+    json: OnceCell<Box<str>>,
+    msgpack: OnceCell<Box<[u8]>>,
+}
+
+impl EncodedMessage {
+    /// Prepares a message to be encoded.
+    ///
+    /// # Arguments
+    ///
+    /// - msg : The message to encode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tchatchers_core::codec::{from_msgpack, EncodedMessage};
+    /// use tchatchers_core::ws_message::WsMessage;
+    ///
+    /// let encoded = EncodedMessage::new(&WsMessage::Pong);
+    /// assert_eq!(encoded.json(), serde_json::to_string(&WsMessage::Pong).unwrap());
+    /// assert_eq!(from_msgpack(encoded.msgpack()).unwrap(), WsMessage::Pong);
+    /// ```
+    pub fn new(msg: &WsMessage) -> Self {
+        Self(Arc::new(LazyEncodings {
+            msg: msg.clone(),
+            json: OnceCell::new(),
+            msgpack: OnceCell::new(),
+        }))
+    }
+
+    /// The message encoded as JSON.
+    pub fn json(&self) -> &str {
+        self.0.json.get_or_init(|| {
+            serde_json::to_string(&self.0.msg)
+                .expect("The messages can always be encoded")
+                .into()
+        })
+    }
+
+    /// The message encoded as MessagePack.
+    pub fn msgpack(&self) -> &[u8] {
+        self.0
+            .msgpack
+            .get_or_init(|| to_msgpack(&self.0.msg).into())
+    }
+}
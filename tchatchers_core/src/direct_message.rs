@@ -0,0 +1,196 @@
+//! Direct messages are private conversations between two users.
+//!
+//! A direct conversation is a room whose name is derived from the ids of both
+//! participants, so that both of them always end up in the same room while
+//! the other users are denied access to it.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use crate::user::PartialUser;
+use crate::ws_message::WsMessageContent;
+
+/// The prefix of the rooms used for direct conversations.
+pub const DM_ROOM_PREFIX: &str = "dm-";
+
+/// Returns the name of the room used for the direct conversation between two
+/// users.
+///
+/// The name doesn't depend on the order of the users.
+///
+/// # Arguments
+///
+/// - user_id : One of the participants.
+/// - other_id : The other participant.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::direct_message::dm_room_name;
+///
+/// assert_eq!(dm_room_name(12, 3), "dm-3-12");
+/// assert_eq!(dm_room_name(3, 12), dm_room_name(12, 3));
+/// ```
+pub fn dm_room_name(user_id: i32, other_id: i32) -> String {
+    format!(
+        "{DM_ROOM_PREFIX}{}-{}",
+        user_id.min(other_id),
+        user_id.max(other_id)
+    )
+}
+
+/// Returns the ids of the participants of a direct conversation, or `None` if
+/// the room isn't one.
+///
+/// # Arguments
+///
+/// - room : The room name.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::direct_message::dm_participants;
+///
+/// assert_eq!(dm_participants("dm-3-12"), Some((3, 12)));
+/// assert_eq!(dm_participants("dm-12-3"), None);
+/// assert_eq!(dm_participants("general"), None);
+/// ```
+pub fn dm_participants(room: &str) -> Option<(i32, i32)> {
+    let (first, second) = room.strip_prefix(DM_ROOM_PREFIX)?.split_once('-')?;
+    let (first, second) = (first.parse().ok()?, second.parse().ok()?);
+    // Only the canonical name designates the conversation, the other spellings
+    // are regular rooms.
+    (dm_room_name(first, second) == room).then_some((first, second))
+}
+
+/// Whether the user is allowed to access the room.
+///
+/// Any user can access a regular room, while the direct conversations are
+/// restricted to their participants.
+///
+/// # Arguments
+///
+/// - room : The room name.
+/// - user_id : The user attempting to access the room.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::direct_message::can_access_room;
+///
+/// assert!(can_access_room("general", 3));
+/// assert!(can_access_room("dm-3-12", 3));
+/// assert!(!can_access_room("dm-3-12", 4));
+/// assert!(can_access_room("dm-12-3", 4));
+/// ```
+pub fn can_access_room(room: &str, user_id: i32) -> bool {
+    match dm_participants(room) {
+        Some((first, second)) => user_id == first || user_id == second,
+        None => true,
+    }
+}
+
+/// Builds the SQL condition checking whether the user is allowed to access
+/// the room, following the same rules as `can_access_room`.
+///
+/// # Arguments
+///
+/// - room : The expression giving the room name.
+/// - user_id : The expression giving the user attempting to access the room.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub(crate) fn can_access_room_condition(room: &str, user_id: &str) -> String {
+    let (first, second) = (
+        format!("split_part({room}, '-', 2)::BIGINT"),
+        format!("split_part({room}, '-', 3)::BIGINT"),
+    );
+    // The ids are only parsed once the name is known to contain them, and
+    // only the canonical names designate a conversation.
+    format!(
+        "CASE WHEN {room} ~ '^{DM_ROOM_PREFIX}(0|[1-9][0-9]{{0,9}})-(0|[1-9][0-9]{{0,9}})$' THEN
+            CASE WHEN {first} <= {second} AND {second} <= {max} THEN {user_id} IN ({first}, {second})
+            ELSE TRUE END
+        ELSE TRUE END",
+        max = i32::MAX
+    )
+}
+
+/// A direct conversation, as seen by one of its participants.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectConversation {
+    /// The room used for the conversation.
+    pub room: String,
+    /// The other participant of the conversation.
+    pub interlocutor: PartialUser,
+    /// The most recent message of the conversation, if any has been sent.
+    pub last_message: Option<WsMessageContent>,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl DirectConversation {
+    /// Returns the conversation between the user and the interlocutor.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user requesting the conversation.
+    /// - interlocutor : The other participant.
+    /// - pool : The connection pool.
+    pub async fn between(
+        user_id: i32,
+        interlocutor: PartialUser,
+        pool: &sqlx::PgPool,
+    ) -> Result<Self, sqlx::Error> {
+        let room = dm_room_name(user_id, interlocutor.id);
+        let page = WsMessageContent::query_page_for_room(&room, None, 1, pool).await?;
+        Ok(Self {
+            room,
+            interlocutor,
+            last_message: page.messages.into_iter().next(),
+        })
+    }
+
+    /// Returns the conversations the user took part in, from the most
+    /// recently active to the oldest.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user whose conversations are returned.
+    /// - pool : The connection pool.
+    pub async fn list_for_user(
+        user_id: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let messages = WsMessageContent::query_last_direct_messages(user_id, pool).await?;
+        let interlocutor_ids: Vec<i32> = messages
+            .iter()
+            .filter_map(|message| interlocutor_id(&message.room, user_id))
+            .collect();
+        let interlocutors = PartialUser::find_by_ids(&interlocutor_ids, pool).await?;
+        Ok(messages
+            .into_iter()
+            .filter_map(|message| {
+                let interlocutor_id = interlocutor_id(&message.room, user_id)?;
+                let interlocutor = interlocutors
+                    .iter()
+                    .find(|interlocutor| interlocutor.id == interlocutor_id)?
+                    .clone();
+                Some(Self {
+                    room: message.room.clone(),
+                    interlocutor,
+                    last_message: Some(message),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Returns the id of the other participant of the user's conversation, if the
+/// user takes part in it.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub(crate) fn interlocutor_id(room: &str, user_id: i32) -> Option<i32> {
+    match dm_participants(room)? {
+        (first, second) if first == user_id => Some(second),
+        (first, second) if second == user_id => Some(first),
+        _ => None,
+    }
+}
@@ -0,0 +1,356 @@
+//! Filter rules are set by the administrators to keep the slurs and the spam
+//! out of the rooms.
+//!
+//! Each rule matches a pattern against the content of the messages, and
+//! either rejects the message, masks the matching text, or flags the message
+//! so that it can be reviewed.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+#[cfg(feature = "back")]
+use crate::manager::ManagerError;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// How the pattern of a rule is matched against the messages.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    derive_more::Display,
+)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::Type))]
+#[serde(rename_all = "camelCase")]
+#[repr(i32)]
+pub enum FilterMatchMode {
+    /// The pattern has to match whole words, whatever their case.
+    Word = 1,
+    /// The pattern can match any part of the message, whatever its case.
+    Substring = 2,
+    /// The pattern is a regular expression.
+    Regex = 3,
+}
+
+/// What happens to a message matched by a rule.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    derive_more::Display,
+)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::Type))]
+#[serde(rename_all = "camelCase")]
+#[repr(i32)]
+pub enum FilterAction {
+    /// The message isn't sent.
+    Reject = 1,
+    /// The matching text is replaced by asterisks.
+    Mask = 2,
+    /// The message is sent, but recorded for the moderators to review it.
+    Flag = 3,
+}
+
+/// A rule applied to the messages before they are sent.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct FilterRule {
+    /// The rule identifier.
+    pub id: i32,
+    /// The pattern matched against the messages.
+    pub pattern: String,
+    /// How the pattern is matched.
+    pub match_mode: FilterMatchMode,
+    /// What happens to the messages matched.
+    pub action: FilterAction,
+    /// When the rule has been created.
+    pub created_at: DateTime<Utc>,
+}
+
+/// Structure used only to create new rules.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Validate)]
+#[serde(rename_all = "camelCase")]
+#[validate(schema(function = "compiles"))]
+pub struct InsertableFilterRule {
+    /// The pattern matched against the messages.
+    #[validate(length(min = 1, max = 127))]
+    pub pattern: String,
+    /// How the pattern is matched.
+    pub match_mode: FilterMatchMode,
+    /// What happens to the messages matched.
+    pub action: FilterAction,
+}
+
+fn compiles(rule: &InsertableFilterRule) -> Result<(), ValidationError> {
+    match build_regex(&rule.pattern, rule.match_mode) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("invalid_pattern")),
+    }
+}
+
+/// Builds the regular expression matching the pattern of a rule.
+///
+/// # Arguments
+///
+/// - pattern : The pattern of the rule.
+/// - match_mode : How the pattern is matched.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::filter::{build_regex, FilterMatchMode};
+///
+/// let word = build_regex("spam", FilterMatchMode::Word).unwrap();
+/// assert!(word.is_match("No SPAM here"));
+/// assert!(!word.is_match("spammer"));
+///
+/// let substring = build_regex("spam", FilterMatchMode::Substring).unwrap();
+/// assert!(substring.is_match("spammer"));
+///
+/// assert!(build_regex("(unclosed", FilterMatchMode::Regex).is_err());
+/// ```
+pub fn build_regex(pattern: &str, match_mode: FilterMatchMode) -> Result<Regex, regex::Error> {
+    match match_mode {
+        FilterMatchMode::Word => Regex::new(&format!(r"(?i)\b{}\b", regex::escape(pattern))),
+        FilterMatchMode::Substring => Regex::new(&format!("(?i){}", regex::escape(pattern))),
+        FilterMatchMode::Regex => Regex::new(pattern),
+    }
+}
+
+/// The result of the filtering of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterOutcome {
+    /// The message matched a rule rejecting it.
+    Rejected,
+    /// The message can be sent.
+    Accepted {
+        /// The content of the message, masked if needed.
+        content: String,
+        /// The rules that flagged the message.
+        flagged_by: Vec<i32>,
+    },
+}
+
+/// A message flagged by a rule, waiting to be reviewed.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct FlaggedMessage {
+    /// The message flagged.
+    pub message_uuid: Uuid,
+    /// The room the message has been sent in.
+    pub room: String,
+    /// The content of the message.
+    pub content: String,
+    /// The name of the message's author.
+    pub author_name: String,
+    /// The pattern of the rule that flagged the message.
+    pub pattern: String,
+    /// When the message has been flagged.
+    pub flagged_at: DateTime<Utc>,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl FilterRule {
+    /// Returns all the rules, from the oldest to the most recent.
+    ///
+    /// # Arguments
+    ///
+    /// - pool : The connection pool.
+    pub async fn find_all(pool: &sqlx::PgPool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, pattern, match_mode, action, created_at FROM FILTER_RULE ORDER BY id",
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Creates a rule.
+    ///
+    /// # Arguments
+    ///
+    /// - rule : The rule to create.
+    /// - created_by : The administrator creating the rule.
+    /// - pool : The connection pool.
+    pub async fn insert(
+        rule: &InsertableFilterRule,
+        created_by: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as(
+            "INSERT INTO FILTER_RULE(pattern, match_mode, action, created_by) VALUES ($1,$2,$3,$4)
+            RETURNING id, pattern, match_mode, action, created_at",
+        )
+        .bind(&rule.pattern)
+        .bind(rule.match_mode)
+        .bind(rule.action)
+        .bind(created_by)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Deletes a rule, returning whether it existed.
+    ///
+    /// # Arguments
+    ///
+    /// - id : The rule identifier.
+    /// - pool : The connection pool.
+    pub async fn delete(id: i32, pool: &sqlx::PgPool) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM FILTER_RULE WHERE id=$1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records that a message has been flagged by rules.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message flagged.
+    /// - rule_ids : The rules that flagged the message.
+    /// - pool : The connection pool.
+    pub async fn flag_message(
+        message_uuid: Uuid,
+        rule_ids: &[i32],
+        pool: &sqlx::PgPool,
+    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO MESSAGE_FLAG(message_uuid, rule_id)
+            SELECT $1, id FROM FILTER_RULE WHERE id = ANY($2)
+            ON CONFLICT DO NOTHING",
+        )
+        .bind(message_uuid)
+        .bind(rule_ids)
+        .execute(pool)
+        .await
+    }
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl FlaggedMessage {
+    /// Returns the messages flagged, from the most recent to the oldest.
+    ///
+    /// The deleted messages are never returned.
+    ///
+    /// # Arguments
+    ///
+    /// - pool : The connection pool.
+    pub async fn find_all(pool: &sqlx::PgPool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT f.message_uuid, m.room, m.content, c.name AS author_name, r.pattern, f.flagged_at
+            FROM MESSAGE_FLAG f
+            INNER JOIN MESSAGE m ON f.message_uuid = m.uuid
+            INNER JOIN CHATTER c ON m.author = c.id
+            INNER JOIN FILTER_RULE r ON f.rule_id = r.id
+            WHERE m.deleted_at IS NULL
+            ORDER BY f.flagged_at DESC",
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Server side cached filter rules.
+///
+/// The rules are compiled once when the manager is initialized.
+#[derive(Clone, Debug)]
+#[cfg(feature = "back")]
+pub struct FilterManager {
+    /// Whether this has been initialized.
+    init: bool,
+    /// The cached rules, along with the expression matching them.
+    rules: Vec<(FilterRule, Regex)>,
+}
+
+#[cfg(feature = "back")]
+impl FilterManager {
+    /// Initializes the manager.
+    ///
+    /// The manager isn't initialized if the rules can't be retrieved, while
+    /// the rules whose pattern doesn't compile are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// - pool : The postgres pool.
+    pub async fn init(pool: &sqlx::PgPool) -> FilterManager {
+        let Ok(rules) = FilterRule::find_all(pool).await else {
+            return FilterManager {
+                init: false,
+                rules: vec![],
+            };
+        };
+        FilterManager {
+            init: true,
+            rules: rules
+                .into_iter()
+                .filter_map(|rule| {
+                    let regex = build_regex(&rule.pattern, rule.match_mode).ok()?;
+                    Some((rule, regex))
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the rules cached.
+    pub fn get_rules(&self) -> Result<Vec<FilterRule>, ManagerError<i32>> {
+        if !self.init {
+            return Err(ManagerError::NotInit);
+        }
+        Ok(self.rules.iter().map(|(rule, _)| rule.clone()).collect())
+    }
+
+    /// Applies the rules to the content of a message.
+    ///
+    /// All the rules are matched against the original content, so that a
+    /// masked text can't escape the other rules.
+    ///
+    /// # Arguments
+    ///
+    /// - content : The content of the message.
+    pub fn apply(&self, content: &str) -> Result<FilterOutcome, ManagerError<i32>> {
+        if !self.init {
+            return Err(ManagerError::NotInit);
+        }
+        let matching = || {
+            self.rules
+                .iter()
+                .filter(|(_, regex)| regex.is_match(content))
+        };
+        if matching().any(|(rule, _)| rule.action == FilterAction::Reject) {
+            return Ok(FilterOutcome::Rejected);
+        }
+        let mut masked = content.to_string();
+        let mut flagged_by = vec![];
+        for (rule, regex) in matching() {
+            match rule.action {
+                FilterAction::Mask => {
+                    masked = regex
+                        .replace_all(&masked, |captures: &regex::Captures| {
+                            "*".repeat(captures[0].chars().count())
+                        })
+                        .into_owned();
+                }
+                FilterAction::Flag => flagged_by.push(rule.id),
+                FilterAction::Reject => {}
+            }
+        }
+        Ok(FilterOutcome::Accepted {
+            content: masked,
+            flagged_by,
+        })
+    }
+}
@@ -2,17 +2,31 @@
 //! the client and the server applications.
 
 pub mod app_context;
+pub mod attachment;
 pub mod authorization_token;
+pub mod codec;
 pub(crate) mod common;
+pub mod direct_message;
+pub mod filter;
 pub mod locale;
 #[cfg(feature = "back")]
 pub mod manager;
+pub mod markdown;
+pub mod mention;
 pub mod navlink;
+pub mod pin;
 #[cfg(any(feature = "back", feature = "cli"))]
 pub mod pool;
 pub mod profile;
+pub mod protocol;
+pub mod rate_limit;
+pub mod reaction;
+pub mod read_marker;
+pub mod receipt;
 pub mod refresh_token;
+pub mod retention;
 pub mod room;
+pub mod scheduled_message;
 pub mod serializable_token;
 pub mod timezone;
 pub mod translation;
@@ -0,0 +1,381 @@
+//! A lightweight markup used to format the content of the messages.
+//!
+//! The following subset is supported :
+//!
+//! - `**bold**`
+//! - `*italic*`
+//! - `~~strikethrough~~`
+//! - `` `inline code` ``
+//! - fenced code blocks, opened and closed by a line made of ```` ``` ````
+//! - `[links](https://example.com)`
+//!
+//! The parser never produces HTML, it returns a tree that the clients render
+//! with their own toolkit. This way, whatever a user sends can't be
+//! interpreted as markup by the browser. Links are only kept when their
+//! scheme is known to be safe, they are displayed as plain text otherwise.
+//!
+//! ```
+//! use tchatchers_core::markdown::{parse, Block, Inline};
+//!
+//! assert_eq!(
+//!     parse("Hello **world**"),
+//!     vec![Block::Paragraph(vec![
+//!         Inline::Text("Hello ".into()),
+//!         Inline::Bold(vec![Inline::Text("world".into())]),
+//!     ])]
+//! );
+//! ```
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+/// The maximum depth of nested styles, the deeper delimiters are kept as text.
+const MAX_NESTING: usize = 8;
+
+/// The schemes a link can use.
+const ALLOWED_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+/// The delimiters of the styles applied to a span of text, the longest first.
+const STYLE_DELIMITERS: [&str; 3] = ["**", "~~", "*"];
+
+/// A block of a formatted message.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Block {
+    /// Consecutive lines of formatted text.
+    Paragraph(Vec<Inline>),
+    /// A fenced code block, displayed as is.
+    Code {
+        language: Option<String>,
+        code: String,
+    },
+}
+
+/// A span of formatted text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Inline {
+    /// Plain text.
+    Text(String),
+    /// A line break within a paragraph.
+    LineBreak,
+    /// Inline code, displayed as is.
+    Code(String),
+    /// Text in bold.
+    Bold(Vec<Inline>),
+    /// Text in italic.
+    Italic(Vec<Inline>),
+    /// Struck text.
+    Strikethrough(Vec<Inline>),
+    /// A link to an external resource.
+    Link { url: String, label: Vec<Inline> },
+}
+
+/// Parses the content of a message.
+///
+/// The parsing never fails, anything that isn't recognized is kept as text.
+///
+/// # Arguments
+///
+/// - input : The content to parse.
+///
+/// # Examples
+///
+/// HTML is never interpreted :
+///
+/// ```
+/// use tchatchers_core::markdown::{parse, Block, Inline};
+///
+/// assert_eq!(
+///     parse("<script>alert(1)</script>"),
+///     vec![Block::Paragraph(vec![Inline::Text("<script>alert(1)</script>".into())])]
+/// );
+/// ```
+///
+/// Links using an unsafe scheme are kept as text, whatever their case :
+///
+/// ```
+/// use tchatchers_core::markdown::{parse, Block, Inline};
+///
+/// for input in [
+///     "[click](javascript:alert(1))",
+///     "[click](JaVaScRiPt:alert(1))",
+///     "[click](data:text/html;base64,PHNjcmlwdD4=)",
+///     "[click](//evil.com)",
+///     "[click](https://example.com\" onclick=\"alert(1))",
+/// ] {
+///     assert_eq!(parse(input), vec![Block::Paragraph(vec![Inline::Text(input.into())])]);
+/// }
+///
+/// assert_eq!(
+///     parse("[docs](https://example.com)"),
+///     vec![Block::Paragraph(vec![Inline::Link {
+///         url: "https://example.com".into(),
+///         label: vec![Inline::Text("docs".into())],
+///     }])]
+/// );
+/// ```
+///
+/// Unterminated delimiters are kept as text :
+///
+/// ```
+/// use tchatchers_core::markdown::{parse, Block, Inline};
+///
+/// for input in ["**bold", "~~", "`code", "[label](https://example.com", "2 * 3 * 4"] {
+///     assert_eq!(parse(input), vec![Block::Paragraph(vec![Inline::Text(input.into())])]);
+/// }
+/// ```
+///
+/// Nothing is parsed within code :
+///
+/// ```
+/// use tchatchers_core::markdown::{parse, Block, Inline};
+///
+/// assert_eq!(
+///     parse("`**[x](https://example.com)**`"),
+///     vec![Block::Paragraph(vec![Inline::Code("**[x](https://example.com)**".into())])]
+/// );
+/// assert_eq!(
+///     parse("```rust\nlet a = *b;\n<b>\n```"),
+///     vec![Block::Code {
+///         language: Some("rust".into()),
+///         code: "let a = *b;\n<b>".into(),
+///     }]
+/// );
+/// ```
+///
+/// Styles can be nested, and escaped delimiters are kept as text :
+///
+/// ```
+/// use tchatchers_core::markdown::{parse, Block, Inline};
+///
+/// assert_eq!(
+///     parse("~~**a *b* c**~~"),
+///     vec![Block::Paragraph(vec![Inline::Strikethrough(vec![Inline::Bold(vec![
+///         Inline::Text("a ".into()),
+///         Inline::Italic(vec![Inline::Text("b".into())]),
+///         Inline::Text(" c".into()),
+///     ])])])]
+/// );
+/// assert_eq!(
+///     parse(r"\*not italic\*"),
+///     vec![Block::Paragraph(vec![Inline::Text("*not italic*".into())])]
+/// );
+/// ```
+///
+/// Hostile inputs are parsed without failing :
+///
+/// ```
+/// use tchatchers_core::markdown::parse;
+///
+/// parse(&"*~~**[`".repeat(1_000));
+/// parse(&format!("{}x{}", "[".repeat(1_000), "](https://example.com)".repeat(1_000)));
+/// ```
+pub fn parse(input: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+    let mut lines = input.lines();
+    while let Some(line) = lines.next() {
+        if let Some(language) = line.trim_start().strip_prefix("```") {
+            push_paragraph(&mut blocks, &mut paragraph);
+            let mut code: Vec<&str> = vec![];
+            for line in lines.by_ref() {
+                if line.trim() == "```" {
+                    break;
+                }
+                code.push(line);
+            }
+            let language = language.trim();
+            blocks.push(Block::Code {
+                language: (!language.is_empty()).then(|| language.to_string()),
+                code: code.join("\n"),
+            });
+        } else if line.trim().is_empty() {
+            push_paragraph(&mut blocks, &mut paragraph);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    push_paragraph(&mut blocks, &mut paragraph);
+    blocks
+}
+
+/// Whether a link can point to the given URL.
+///
+/// Only the web and mail schemes are accepted, and the URL can't contain any
+/// character that would let it escape an attribute.
+///
+/// # Arguments
+///
+/// - url : The URL to check.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::markdown::is_safe_url;
+///
+/// assert!(is_safe_url("https://example.com/path?query=1"));
+/// assert!(is_safe_url("mailto:someone@example.com"));
+/// assert!(!is_safe_url("javascript:alert(1)"));
+/// assert!(!is_safe_url(" javascript:alert(1)"));
+/// assert!(!is_safe_url("vbscript:msgbox(1)"));
+/// assert!(!is_safe_url("https://"));
+/// assert!(!is_safe_url("https://example.com/<script>"));
+/// assert!(!is_safe_url("https://exa mple.com"));
+/// ```
+pub fn is_safe_url(url: &str) -> bool {
+    let lowercase = url.to_ascii_lowercase();
+    ALLOWED_SCHEMES
+        .iter()
+        .any(|scheme| lowercase.starts_with(scheme) && lowercase.len() > scheme.len())
+        && !url.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '<' | '>' | '`')
+        })
+}
+
+/// Parses the lines of the current paragraph and adds it to the blocks.
+fn push_paragraph(blocks: &mut Vec<Block>, paragraph: &mut Vec<&str>) {
+    if !paragraph.is_empty() {
+        blocks.push(Block::Paragraph(parse_inlines(&paragraph.join("\n"), 0)));
+        paragraph.clear();
+    }
+}
+
+/// Adds the text accumulated so far to the spans.
+fn push_text(inlines: &mut Vec<Inline>, text: &mut String) {
+    if !text.is_empty() {
+        inlines.push(Inline::Text(std::mem::take(text)));
+    }
+}
+
+/// Parses the spans of a paragraph.
+///
+/// # Arguments
+///
+/// - input : The text to parse.
+/// - depth : The number of styles the text is nested in.
+fn parse_inlines(input: &str, depth: usize) -> Vec<Inline> {
+    let mut inlines = vec![];
+    let mut text = String::new();
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if let Some((inline, remaining)) = parse_span(rest, depth) {
+            push_text(&mut inlines, &mut text);
+            inlines.push(inline);
+            rest = remaining;
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\\' => match rest.chars().next() {
+                Some(escaped) if escaped.is_ascii_punctuation() => {
+                    text.push(escaped);
+                    rest = &rest[1..];
+                }
+                _ => text.push(c),
+            },
+            '\n' => {
+                push_text(&mut inlines, &mut text);
+                inlines.push(Inline::LineBreak);
+            }
+            _ => text.push(c),
+        }
+    }
+    push_text(&mut inlines, &mut text);
+    inlines
+}
+
+/// Parses the span starting the input, if any.
+///
+/// Returns the span along with the remaining input.
+///
+/// # Arguments
+///
+/// - input : The text to parse.
+/// - depth : The number of styles the text is nested in.
+fn parse_span(input: &str, depth: usize) -> Option<(Inline, &str)> {
+    if let Some(rest) = input.strip_prefix('`') {
+        let end = rest.find('`').filter(|end| *end > 0)?;
+        return Some((Inline::Code(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if depth >= MAX_NESTING {
+        return None;
+    }
+    if input.starts_with('[') {
+        return parse_link(input, depth);
+    }
+    for delimiter in STYLE_DELIMITERS {
+        let Some(rest) = input.strip_prefix(delimiter) else {
+            continue;
+        };
+        let Some(end) = find_closing_delimiter(rest, delimiter) else {
+            continue;
+        };
+        let content = &rest[..end];
+        if content.is_empty()
+            || content.starts_with(char::is_whitespace)
+            || content.ends_with(char::is_whitespace)
+        {
+            continue;
+        }
+        let children = parse_inlines(content, depth + 1);
+        let inline = match delimiter {
+            "**" => Inline::Bold(children),
+            "~~" => Inline::Strikethrough(children),
+            _ => Inline::Italic(children),
+        };
+        return Some((inline, &rest[end + delimiter.len()..]));
+    }
+    None
+}
+
+/// Parses the link starting the input, if its URL is safe.
+///
+/// # Arguments
+///
+/// - input : The text to parse.
+/// - depth : The number of styles the link is nested in.
+fn parse_link(input: &str, depth: usize) -> Option<(Inline, &str)> {
+    let rest = input.strip_prefix('[')?;
+    let label_end = rest.find("](")?;
+    let label = &rest[..label_end];
+    let after_label = &rest[label_end + 2..];
+    let url_end = after_label.find(')')?;
+    let url = &after_label[..url_end];
+    if label.is_empty() || label.contains('[') || !is_safe_url(url) {
+        return None;
+    }
+    Some((
+        Inline::Link {
+            url: url.to_string(),
+            label: parse_inlines(label, depth + 1),
+        },
+        &after_label[url_end + 1..],
+    ))
+}
+
+/// Returns the position of the delimiter closing a style, ignoring the
+/// escaped ones.
+///
+/// Single character delimiters that are part of a longer one are ignored as
+/// well, so that a bold span can be nested within an italic one.
+///
+/// # Arguments
+///
+/// - input : The text following the opening delimiter.
+/// - delimiter : The delimiter to look for.
+fn find_closing_delimiter(input: &str, delimiter: &str) -> Option<usize> {
+    let bytes = input.as_bytes();
+    let mut from = 0;
+    while let Some(offset) = input[from..].find(delimiter) {
+        let index = from + offset;
+        let end = index + delimiter.len();
+        let is_escaped = index > 0 && bytes[index - 1] == b'\\';
+        let is_doubled = delimiter.len() == 1
+            && (bytes.get(end) == Some(&bytes[index])
+                || (index > 0 && bytes[index - 1] == bytes[index]));
+        if !is_escaped && !is_doubled {
+            return Some(index);
+        }
+        from = end;
+    }
+    None
+}
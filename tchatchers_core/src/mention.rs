@@ -0,0 +1,170 @@
+//! Mentions are used to notify a user that a message concerns them.
+//!
+//! A user is mentioned by writing their login preceded by an `@`, the
+//! mentions written within code are ignored.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use crate::markdown::{parse, Block, Inline};
+
+#[cfg(any(feature = "back", feature = "cli"))]
+use uuid::Uuid;
+
+/// The minimum number of characters of a login.
+const MIN_LOGIN_LENGTH: usize = 3;
+
+/// The maximum number of characters of a login.
+const MAX_LOGIN_LENGTH: usize = 32;
+
+/// A chunk of text, split around the mentions it contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextSegment<'a> {
+    /// Text without any mention.
+    Text(&'a str),
+    /// The login of a mentioned user, without the leading `@`.
+    Mention(&'a str),
+}
+
+/// Whether the character can be part of a login.
+fn is_login_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Splits a text around the mentions it contains.
+///
+/// An `@` is only considered as a mention when it isn't preceded by a
+/// character of a login, so that email addresses aren't mistaken for
+/// mentions.
+///
+/// # Arguments
+///
+/// - text : The text to split.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::mention::{split_mentions, TextSegment};
+///
+/// assert_eq!(
+///     split_mentions("Hi @john_doe, mail me at jane@example.com"),
+///     vec![
+///         TextSegment::Text("Hi "),
+///         TextSegment::Mention("john_doe"),
+///         TextSegment::Text(", mail me at jane@example.com"),
+///     ]
+/// );
+/// ```
+pub fn split_mentions(text: &str) -> Vec<TextSegment<'_>> {
+    let mut segments = vec![];
+    let mut text_start = 0;
+    let mut index = 0;
+    while let Some(c) = text[index..].chars().next() {
+        let is_preceded_by_login = text[..index]
+            .chars()
+            .next_back()
+            .map(is_login_char)
+            .unwrap_or(false);
+        if c == '@' && !is_preceded_by_login {
+            let login_start = index + 1;
+            let login_end = text[login_start..]
+                .find(|c: char| !is_login_char(c))
+                .map(|offset| login_start + offset)
+                .unwrap_or(text.len());
+            if (MIN_LOGIN_LENGTH..=MAX_LOGIN_LENGTH).contains(&(login_end - login_start)) {
+                if text_start < index {
+                    segments.push(TextSegment::Text(&text[text_start..index]));
+                }
+                segments.push(TextSegment::Mention(&text[login_start..login_end]));
+                text_start = login_end;
+                index = login_end;
+                continue;
+            }
+        }
+        index += c.len_utf8();
+    }
+    if text_start < text.len() {
+        segments.push(TextSegment::Text(&text[text_start..]));
+    }
+    segments
+}
+
+/// Returns the logins mentioned in the content of a message, without
+/// duplicates.
+///
+/// # Arguments
+///
+/// - content : The content of the message.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::mention::mentioned_logins;
+///
+/// assert_eq!(
+///     mentioned_logins("**@alice** and @bob, not `@carol`, again @alice"),
+///     vec!["alice".to_string(), "bob".to_string()]
+/// );
+/// ```
+pub fn mentioned_logins(content: &str) -> Vec<String> {
+    let mut logins: Vec<String> = vec![];
+    for block in parse(content) {
+        if let Block::Paragraph(inlines) = block {
+            collect_mentions(&inlines, &mut logins);
+        }
+    }
+    logins
+}
+
+/// Adds the logins mentioned in the spans to the list.
+fn collect_mentions(inlines: &[Inline], logins: &mut Vec<String>) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => {
+                for segment in split_mentions(text) {
+                    if let TextSegment::Mention(login) = segment {
+                        if !logins.iter().any(|known| known == login) {
+                            logins.push(login.to_string());
+                        }
+                    }
+                }
+            }
+            Inline::Bold(children)
+            | Inline::Italic(children)
+            | Inline::Strikethrough(children)
+            | Inline::Link {
+                label: children, ..
+            } => collect_mentions(children, logins),
+            Inline::LineBreak | Inline::Code(_) => {}
+        }
+    }
+}
+
+/// Records the users mentioned in a message.
+///
+/// Returns the ids of the users mentioned, the unknown logins and the author
+/// of the message being ignored.
+///
+/// # Arguments
+///
+/// - message_uuid : The message containing the mentions.
+/// - logins : The logins mentioned.
+/// - pool : The connection pool.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub async fn persist_mentions(
+    message_uuid: Uuid,
+    logins: &[String],
+    pool: &sqlx::PgPool,
+) -> Result<Vec<i32>, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO MENTION(message_uuid, user_id)
+        SELECT m.uuid, c.id FROM MESSAGE m INNER JOIN CHATTER c ON c.login = ANY($2) AND c.id != m.author
+        WHERE m.uuid = $1
+        ON CONFLICT DO NOTHING
+        RETURNING user_id",
+    )
+    .bind(message_uuid)
+    .bind(logins)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,146 @@
+//! Pins highlight the messages of a room that have to remain visible, such as
+//! its rules or the announcements.
+//!
+//! Only the moderators can pin messages, along with the participants of a
+//! direct conversation, who own it.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use crate::direct_message::dm_participants;
+use crate::profile::Profile;
+use crate::user::PartialUser;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A message pinned in a room.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedMessage {
+    /// The identifier of the message pinned.
+    pub uuid: Uuid,
+    /// The name of the message's author.
+    pub author_name: String,
+    /// The content of the message.
+    pub content: String,
+    /// The name of the user who pinned the message.
+    pub pinned_by: String,
+    /// When the message has been pinned.
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// The result of the pinning of a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinOutcome {
+    /// The message has been pinned.
+    Pinned,
+    /// The message was already pinned, nothing has changed.
+    AlreadyPinned,
+    /// The message can't be found in the room.
+    NotFound,
+}
+
+/// Whether the user can pin and unpin the messages of the room.
+///
+/// # Arguments
+///
+/// - room : The room name.
+/// - user : The user attempting to pin a message.
+pub fn can_pin(room: &str, user: &PartialUser) -> bool {
+    user.profile >= Profile::Moderator
+        || dm_participants(room)
+            .map(|(first, second)| user.id == first || user.id == second)
+            .unwrap_or(false)
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl PinnedMessage {
+    /// Returns the messages pinned in a room, from the most recently pinned
+    /// to the oldest.
+    ///
+    /// The deleted messages are never returned.
+    ///
+    /// # Arguments
+    ///
+    /// - room_name : The room the pins are looked up for.
+    /// - pool : The connection pool.
+    pub async fn find_for_room(
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT m.uuid, c.name AS author_name, m.content, pc.name AS pinned_by, p.pinned_at
+            FROM ROOM_PIN p
+            INNER JOIN MESSAGE m ON p.message_uuid = m.uuid
+            INNER JOIN CHATTER c ON m.author = c.id
+            INNER JOIN CHATTER pc ON p.pinned_by = pc.id
+            WHERE p.room=$1 AND m.deleted_at IS NULL
+            ORDER BY p.pinned_at DESC",
+        )
+        .bind(room_name)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Pins a message in a room.
+    ///
+    /// Pinning a message that is already pinned has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message to pin.
+    /// - pinned_by : The user pinning the message.
+    /// - room_name : The room the message has to belong to.
+    /// - pool : The connection pool.
+    pub async fn pin(
+        message_uuid: Uuid,
+        pinned_by: i32,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<PinOutcome, sqlx::Error> {
+        let (found, pinned): (bool, bool) = sqlx::query_as(
+            "WITH message AS (
+                SELECT room, uuid FROM MESSAGE WHERE uuid=$1 AND room=$3 AND deleted_at IS NULL
+            ), pinned AS (
+                INSERT INTO ROOM_PIN(room, message_uuid, pinned_by)
+                SELECT room, uuid, $2 FROM message
+                ON CONFLICT DO NOTHING
+                RETURNING message_uuid
+            )
+            SELECT EXISTS(SELECT 1 FROM message), EXISTS(SELECT 1 FROM pinned)",
+        )
+        .bind(message_uuid)
+        .bind(pinned_by)
+        .bind(room_name)
+        .fetch_one(pool)
+        .await?;
+        Ok(match (found, pinned) {
+            (false, _) => PinOutcome::NotFound,
+            (true, false) => PinOutcome::AlreadyPinned,
+            (true, true) => PinOutcome::Pinned,
+        })
+    }
+
+    /// Unpins a message from a room.
+    ///
+    /// Returns whether the message has been unpinned.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message to unpin.
+    /// - room_name : The room the message is pinned in.
+    /// - pool : The connection pool.
+    pub async fn unpin(
+        message_uuid: Uuid,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM ROOM_PIN WHERE message_uuid=$1 AND room=$2")
+            .bind(message_uuid)
+            .bind(room_name)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
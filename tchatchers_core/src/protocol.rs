@@ -0,0 +1,89 @@
+//! The websocket protocol is versioned so that a client and a server built
+//! from different revisions of the application notice they can't understand
+//! each other.
+//!
+//! The client opens every connection with a `Hello` message holding its
+//! protocol version and the optional features it supports. The server either
+//! answers with its own `Hello`, holding the features both sides support, or
+//! closes the connection with [`WS_CLOSE_INCOMPATIBLE_PROTOCOL`].
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+/// The version of the websocket protocol implemented by this build.
+///
+/// It has to be increased whenever the messages exchanged change in a way the
+/// previous builds can't handle.
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// The oldest version of the protocol this build can still talk to.
+pub const MIN_COMPATIBLE_PROTOCOL_VERSION: u16 = 1;
+
+/// The code of the close frame sent by the server to a client whose protocol
+/// version isn't compatible with its own.
+///
+/// The codes from 4000 to 4999 are reserved for the applications.
+pub const WS_CLOSE_INCOMPATIBLE_PROTOCOL: u16 = 4001;
+
+/// The optional features a peer can support.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    /// The peer handles the notifications of the mentions made in the other
+    /// rooms.
+    Mentions,
+    /// The peer handles the changes of the unread counters.
+    UnreadCounters,
+    /// The peer handles the messages encoded as MessagePack.
+    MessagePack,
+    /// A capability introduced by a more recent build.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The capabilities supported by this build.
+pub const SUPPORTED_CAPABILITIES: [Capability; 3] = [
+    Capability::Mentions,
+    Capability::UnreadCounters,
+    Capability::MessagePack,
+];
+
+/// Whether a peer using the given protocol version can be talked to.
+///
+/// # Arguments
+///
+/// - protocol_version : The version used by the peer.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::protocol::{is_compatible, PROTOCOL_VERSION};
+///
+/// assert!(is_compatible(PROTOCOL_VERSION));
+/// assert!(!is_compatible(0));
+/// assert!(!is_compatible(PROTOCOL_VERSION + 1));
+/// ```
+pub fn is_compatible(protocol_version: u16) -> bool {
+    (MIN_COMPATIBLE_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&protocol_version)
+}
+
+/// Returns the capabilities supported by both this build and the peer.
+///
+/// # Arguments
+///
+/// - requested : The capabilities supported by the peer.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::protocol::{negotiate, Capability};
+///
+/// let requested = [Capability::UnreadCounters, Capability::Unknown, Capability::UnreadCounters];
+/// assert_eq!(negotiate(&requested), vec![Capability::UnreadCounters]);
+/// ```
+pub fn negotiate(requested: &[Capability]) -> Vec<Capability> {
+    SUPPORTED_CAPABILITIES
+        .into_iter()
+        .filter(|capability| requested.contains(capability))
+        .collect()
+}
@@ -0,0 +1,160 @@
+//! Rate limits prevent a single user from flooding the rooms with messages.
+//!
+//! Each user owns a bucket of tokens, one of them being consumed by every
+//! message sent. The bucket refills at a constant pace up to its capacity, so
+//! that short bursts are allowed while the sustained rate stays bounded.
+//!
+//! The buckets are stored in Redis so that they are shared by all the server
+//! instances.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::profile::Profile;
+
+/// Refills the bucket according to the time elapsed since its last update,
+/// then consumes a token if one is available.
+///
+/// Returns 0 if the token has been consumed, or the number of milliseconds
+/// to wait until one is available.
+#[cfg(feature = "back")]
+const TOKEN_BUCKET_SCRIPT: &str = r"
+local capacity = tonumber(ARGV[1])
+local refill_ms = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(bucket[1]) or capacity
+local updated_at = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - updated_at) / refill_ms)
+local retry_after = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+else
+    retry_after = math.ceil((1 - tokens) * refill_ms)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity * refill_ms))
+return retry_after
+";
+
+/// The pace at which a user can send messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RateLimit {
+    /// The number of messages that can be sent in a burst.
+    pub capacity: u32,
+    /// The number of messages that can be sent per minute once the burst is
+    /// over.
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    /// Returns the limit applied to the users of a profile.
+    ///
+    /// It can be overriden with the `MESSAGE_RATE_LIMIT_<PROFILE>`
+    /// environment variable, such as `MESSAGE_RATE_LIMIT_MODERATOR=20/60`.
+    ///
+    /// # Arguments
+    ///
+    /// - profile : The profile of the user.
+    pub fn for_profile(profile: Profile) -> Self {
+        let variable = format!("MESSAGE_RATE_LIMIT_{}", profile.to_string().to_uppercase());
+        std::env::var(variable)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| Self::default_for_profile(profile))
+    }
+
+    /// Returns the limit applied by default to the users of a profile.
+    ///
+    /// # Arguments
+    ///
+    /// - profile : The profile of the user.
+    pub fn default_for_profile(profile: Profile) -> Self {
+        match profile {
+            Profile::User => Self {
+                capacity: 10,
+                per_minute: 30,
+            },
+            Profile::Moderator => Self {
+                capacity: 20,
+                per_minute: 60,
+            },
+            Profile::Admin => Self {
+                capacity: 40,
+                per_minute: 120,
+            },
+        }
+    }
+
+    /// The time needed for a single token to be refilled.
+    pub fn refill_interval(&self) -> Duration {
+        Duration::from_millis(60_000 / u64::from(self.per_minute.max(1)))
+    }
+}
+
+/// Parses a limit written as `<capacity>/<per minute>`.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::rate_limit::RateLimit;
+///
+/// assert_eq!("10/30".parse(), Ok(RateLimit { capacity: 10, per_minute: 30 }));
+/// assert!("10".parse::<RateLimit>().is_err());
+/// assert!("0/30".parse::<RateLimit>().is_err());
+/// ```
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (capacity, per_minute) = s.split_once('/').ok_or_else(|| {
+            format!("The rate limit {s} isn't formatted as <capacity>/<per minute>")
+        })?;
+        let (capacity, per_minute): (u32, u32) = (
+            capacity
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid capacity : {capacity}"))?,
+            per_minute
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid rate : {per_minute}"))?,
+        );
+        match capacity > 0 && per_minute > 0 {
+            true => Ok(Self {
+                capacity,
+                per_minute,
+            }),
+            false => Err(format!("The rate limit {s} must be positive")),
+        }
+    }
+}
+
+#[cfg(feature = "back")]
+impl RateLimit {
+    /// Consumes a token from the user's bucket.
+    ///
+    /// Returns none if the user can send a message, or the time to wait until
+    /// they can.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user sending a message.
+    /// - con : The Redis connection.
+    pub fn try_acquire(
+        &self,
+        user_id: i32,
+        con: &mut redis::Connection,
+    ) -> redis::RedisResult<Option<Duration>> {
+        let retry_after: u64 = redis::Script::new(TOKEN_BUCKET_SCRIPT)
+            .key(format!("rate_limit:message:{user_id}"))
+            .arg(self.capacity)
+            .arg(self.refill_interval().as_millis() as u64)
+            .invoke(con)?;
+        Ok((retry_after > 0).then(|| Duration::from_millis(retry_after)))
+    }
+}
@@ -0,0 +1,310 @@
+//! Reactions are emojis users can attach to messages.
+//!
+//! They are displayed aggregated by emoji under the messages.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+#[cfg(any(feature = "back", feature = "cli"))]
+use uuid::Uuid;
+
+/// The maximum number of characters an emoji can be made of.
+///
+/// Some emojis are made of several characters, such as the flags or the ones
+/// having a skin tone.
+pub const MAX_EMOJI_LENGTH: usize = 8;
+
+/// The emojis proposed to the users when they react to a message.
+pub const DEFAULT_REACTIONS: [&str; 6] = ["👍", "❤️", "😂", "😮", "😢", "🎉"];
+
+/// The ranges of the pictographic characters, as defined by the
+/// `Extended_Pictographic` property of the Unicode emoji data.
+const PICTOGRAPHIC_RANGES: [(char, char); 78] = [
+    ('\u{00A9}', '\u{00A9}'),
+    ('\u{00AE}', '\u{00AE}'),
+    ('\u{203C}', '\u{203C}'),
+    ('\u{2049}', '\u{2049}'),
+    ('\u{2122}', '\u{2122}'),
+    ('\u{2139}', '\u{2139}'),
+    ('\u{2194}', '\u{2199}'),
+    ('\u{21A9}', '\u{21AA}'),
+    ('\u{231A}', '\u{231B}'),
+    ('\u{2328}', '\u{2328}'),
+    ('\u{2388}', '\u{2388}'),
+    ('\u{23CF}', '\u{23CF}'),
+    ('\u{23E9}', '\u{23F3}'),
+    ('\u{23F8}', '\u{23FA}'),
+    ('\u{24C2}', '\u{24C2}'),
+    ('\u{25AA}', '\u{25AB}'),
+    ('\u{25B6}', '\u{25B6}'),
+    ('\u{25C0}', '\u{25C0}'),
+    ('\u{25FB}', '\u{25FE}'),
+    ('\u{2600}', '\u{2605}'),
+    ('\u{2607}', '\u{2612}'),
+    ('\u{2614}', '\u{2685}'),
+    ('\u{2690}', '\u{2705}'),
+    ('\u{2708}', '\u{2712}'),
+    ('\u{2714}', '\u{2714}'),
+    ('\u{2716}', '\u{2716}'),
+    ('\u{271D}', '\u{271D}'),
+    ('\u{2721}', '\u{2721}'),
+    ('\u{2728}', '\u{2728}'),
+    ('\u{2733}', '\u{2734}'),
+    ('\u{2744}', '\u{2744}'),
+    ('\u{2747}', '\u{2747}'),
+    ('\u{274C}', '\u{274C}'),
+    ('\u{274E}', '\u{274E}'),
+    ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'),
+    ('\u{2763}', '\u{2767}'),
+    ('\u{2795}', '\u{2797}'),
+    ('\u{27A1}', '\u{27A1}'),
+    ('\u{27B0}', '\u{27B0}'),
+    ('\u{27BF}', '\u{27BF}'),
+    ('\u{2934}', '\u{2935}'),
+    ('\u{2B05}', '\u{2B07}'),
+    ('\u{2B1B}', '\u{2B1C}'),
+    ('\u{2B50}', '\u{2B50}'),
+    ('\u{2B55}', '\u{2B55}'),
+    ('\u{3030}', '\u{3030}'),
+    ('\u{303D}', '\u{303D}'),
+    ('\u{3297}', '\u{3297}'),
+    ('\u{3299}', '\u{3299}'),
+    ('\u{1F000}', '\u{1F0FF}'),
+    ('\u{1F10D}', '\u{1F10F}'),
+    ('\u{1F12F}', '\u{1F12F}'),
+    ('\u{1F16C}', '\u{1F171}'),
+    ('\u{1F17E}', '\u{1F17F}'),
+    ('\u{1F18E}', '\u{1F18E}'),
+    ('\u{1F191}', '\u{1F19A}'),
+    ('\u{1F1AD}', '\u{1F1E5}'),
+    ('\u{1F201}', '\u{1F20F}'),
+    ('\u{1F21A}', '\u{1F21A}'),
+    ('\u{1F22F}', '\u{1F22F}'),
+    ('\u{1F232}', '\u{1F23A}'),
+    ('\u{1F23C}', '\u{1F23F}'),
+    ('\u{1F249}', '\u{1F3FA}'),
+    ('\u{1F400}', '\u{1F53D}'),
+    ('\u{1F546}', '\u{1F64F}'),
+    ('\u{1F680}', '\u{1F6FF}'),
+    ('\u{1F774}', '\u{1F77F}'),
+    ('\u{1F7D5}', '\u{1F7FF}'),
+    ('\u{1F80C}', '\u{1F80F}'),
+    ('\u{1F848}', '\u{1F84F}'),
+    ('\u{1F85A}', '\u{1F85F}'),
+    ('\u{1F888}', '\u{1F88F}'),
+    ('\u{1F8AE}', '\u{1F8FF}'),
+    ('\u{1F90C}', '\u{1F93A}'),
+    ('\u{1F93C}', '\u{1F945}'),
+    ('\u{1F947}', '\u{1FAFF}'),
+    ('\u{1FC00}', '\u{1FFFD}'),
+];
+
+/// Joins the emojis of a sequence into a single one.
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Whether the character is a pictograph, which can be displayed as an emoji.
+fn is_pictographic(c: char) -> bool {
+    PICTOGRAPHIC_RANGES
+        .iter()
+        .any(|(start, end)| (*start..=*end).contains(&c))
+}
+
+/// Whether the character is a regional indicator, two of them forming a flag.
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Whether the character alters the pictograph preceding it, such as the
+/// emoji presentation selector, the skin tones or the tags of the
+/// subdivision flags.
+fn is_modifier(c: char) -> bool {
+    c == '\u{FE0F}'
+        || ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+        || ('\u{E0020}'..='\u{E007F}').contains(&c)
+}
+
+/// The reactions made with a given emoji on a message.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageReaction {
+    /// The emoji used to react.
+    pub emoji: String,
+    /// The number of users who reacted with this emoji.
+    pub count: i64,
+    /// The users who reacted with this emoji.
+    pub user_ids: Vec<i32>,
+}
+
+impl MessageReaction {
+    /// Whether the given string can be used as a reaction.
+    ///
+    /// Only a single emoji is accepted, so that reactions can't be used to
+    /// write text. It is either a flag or a pictograph, along with its
+    /// modifiers, several of them being possibly joined into one.
+    ///
+    /// # Arguments
+    ///
+    /// - emoji : The reaction to check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tchatchers_core::reaction::MessageReaction;
+    ///
+    /// assert!(MessageReaction::is_valid_emoji("👍"));
+    /// assert!(MessageReaction::is_valid_emoji("❤️"));
+    /// assert!(MessageReaction::is_valid_emoji("👍🏽"));
+    /// assert!(MessageReaction::is_valid_emoji("🇫🇷"));
+    /// assert!(MessageReaction::is_valid_emoji("👨‍👩‍👧"));
+    ///
+    /// assert!(!MessageReaction::is_valid_emoji("a"));
+    /// assert!(!MessageReaction::is_valid_emoji("é"));
+    /// assert!(!MessageReaction::is_valid_emoji("日本語"));
+    /// assert!(!MessageReaction::is_valid_emoji("Привет"));
+    /// assert!(!MessageReaction::is_valid_emoji("👍a"));
+    /// assert!(!MessageReaction::is_valid_emoji("🇫"));
+    /// assert!(!MessageReaction::is_valid_emoji("\u{200D}👍"));
+    /// assert!(!MessageReaction::is_valid_emoji("\u{FE0F}"));
+    /// ```
+    pub fn is_valid_emoji(emoji: &str) -> bool {
+        if !(1..=MAX_EMOJI_LENGTH).contains(&emoji.chars().count()) {
+            return false;
+        }
+        let mut chars = emoji.chars().peekable();
+        loop {
+            match chars.next() {
+                Some(c) if is_regional_indicator(c) => {
+                    if !matches!(chars.next(), Some(c) if is_regional_indicator(c)) {
+                        return false;
+                    }
+                }
+                Some(c) if is_pictographic(c) => {
+                    while chars.next_if(|c| is_modifier(*c)).is_some() {}
+                }
+                _ => return false,
+            }
+            match chars.next() {
+                None => return true,
+                Some(ZERO_WIDTH_JOINER) => continue,
+                Some(_) => return false,
+            }
+        }
+    }
+}
+
+/// Builds the expression aggregating the reactions of a message as JSON.
+///
+/// # Arguments
+///
+/// - message_uuid : The expression giving the identifier of the message.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub(crate) fn aggregate_reactions(message_uuid: &str) -> String {
+    format!(
+        "COALESCE((SELECT json_agg(json_build_object('emoji', r.emoji, 'count', r.count, 'userIds', r.user_ids) ORDER BY r.first_reacted_at)
+        FROM (SELECT emoji, COUNT(*) AS count, array_agg(user_id ORDER BY reacted_at) AS user_ids, MIN(reacted_at) AS first_reacted_at
+            FROM MESSAGE_REACTION WHERE message_uuid = {message_uuid} GROUP BY emoji) r), '[]'::JSON)"
+    )
+}
+
+/// The column containing the aggregated reactions of a message.
+#[cfg(any(feature = "back", feature = "cli"))]
+#[derive(sqlx::FromRow)]
+pub struct ReactionsRow {
+    reactions: sqlx::types::Json<Vec<MessageReaction>>,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl From<ReactionsRow> for Vec<MessageReaction> {
+    fn from(row: ReactionsRow) -> Self {
+        row.reactions.0
+    }
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl MessageReaction {
+    /// Returns the reactions made on a message.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message the reactions are looked up for.
+    /// - pool : The connection pool.
+    pub async fn find_for_message(
+        message_uuid: Uuid,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let row: ReactionsRow = sqlx::query_as(&format!(
+            "SELECT {} AS reactions",
+            aggregate_reactions("$1")
+        ))
+        .bind(message_uuid)
+        .fetch_one(pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    /// Adds a reaction of a user on a message.
+    ///
+    /// Returns whether the reaction has been added, it isn't if the user
+    /// already reacted with the same emoji, or if the message can't be found
+    /// in the room.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message reacted to.
+    /// - user_id : The user reacting.
+    /// - emoji : The emoji used to react.
+    /// - room_name : The room the message has to belong to.
+    /// - pool : The connection pool.
+    pub async fn add(
+        message_uuid: Uuid,
+        user_id: i32,
+        emoji: &str,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO MESSAGE_REACTION(message_uuid, user_id, emoji)
+            SELECT uuid, $2, $3 FROM MESSAGE WHERE uuid=$1 AND room=$4 AND deleted_at IS NULL
+            ON CONFLICT DO NOTHING",
+        )
+        .bind(message_uuid)
+        .bind(user_id)
+        .bind(emoji)
+        .bind(room_name)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes the reaction of a user on a message.
+    ///
+    /// Returns whether a reaction has been removed.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message reacted to.
+    /// - user_id : The user who reacted.
+    /// - emoji : The emoji used to react.
+    /// - room_name : The room the message has to belong to.
+    /// - pool : The connection pool.
+    pub async fn remove(
+        message_uuid: Uuid,
+        user_id: i32,
+        emoji: &str,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM MESSAGE_REACTION r USING MESSAGE m
+            WHERE r.message_uuid = m.uuid AND r.message_uuid=$1 AND r.user_id=$2 AND r.emoji=$3 AND m.room=$4",
+        )
+        .bind(message_uuid)
+        .bind(user_id)
+        .bind(emoji)
+        .bind(room_name)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
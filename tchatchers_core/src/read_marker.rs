@@ -0,0 +1,171 @@
+//! Read markers remember, for each user and room, the most recent message the
+//! user has read.
+//!
+//! They are moved forward as the messages are marked as seen, and are used to
+//! count the messages a user hasn't read yet in the rooms they follow.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+#[cfg(any(feature = "back", feature = "cli"))]
+use crate::direct_message::{interlocutor_id, DM_ROOM_PREFIX};
+#[cfg(any(feature = "back", feature = "cli"))]
+use crate::user::PartialUser;
+use uuid::Uuid;
+
+/// The number of messages a user hasn't read yet in a room.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadCount {
+    /// The room name.
+    pub room: String,
+    /// The number of messages written by other users since the last one read.
+    pub unread: i64,
+    /// The last message read by the user, if they read any.
+    pub last_read: Option<Uuid>,
+    /// The login of the other participant, if the room is a direct
+    /// conversation.
+    #[cfg_attr(any(feature = "back", feature = "cli"), sqlx(default))]
+    pub interlocutor: Option<String>,
+}
+
+/// The position of a user in the history of a room.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub struct ReadMarker;
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl ReadMarker {
+    /// Moves the marker of the user to the most recent of the messages read.
+    ///
+    /// The marker never moves backward. Returns the number of messages
+    /// written by other users that are now read, so that the unread counter
+    /// can be decreased.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user reading the messages.
+    /// - room_name : The room the messages belong to.
+    /// - messages_uuid : The messages read.
+    /// - pool : The connection pool.
+    pub async fn advance(
+        user_id: i32,
+        room_name: &str,
+        messages_uuid: &[Uuid],
+        pool: &sqlx::PgPool,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "WITH target AS (
+                SELECT uuid, timestamp FROM MESSAGE WHERE uuid = ANY($1) AND room = $3
+                ORDER BY timestamp DESC
+                LIMIT 1
+            ), previous AS (
+                SELECT last_read_at FROM READ_MARKER WHERE user_id = $2 AND room = $3
+            ), advanced AS (
+                INSERT INTO READ_MARKER(user_id, room, last_read_uuid, last_read_at)
+                SELECT $2, $3, uuid, timestamp FROM target
+                ON CONFLICT (user_id, room) DO UPDATE
+                SET last_read_uuid = EXCLUDED.last_read_uuid, last_read_at = EXCLUDED.last_read_at
+                WHERE READ_MARKER.last_read_at < EXCLUDED.last_read_at
+                RETURNING last_read_at
+            )
+            SELECT COUNT(m.uuid) FROM advanced a
+            INNER JOIN MESSAGE m ON m.room = $3 AND m.timestamp <= a.last_read_at
+            WHERE m.author != $2
+            AND m.deleted_at IS NULL
+            AND m.timestamp > COALESCE((SELECT last_read_at FROM previous), '-infinity')",
+        )
+        .bind(messages_uuid)
+        .bind(user_id)
+        .bind(room_name)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Returns the users following a room, apart from the given one.
+    ///
+    /// # Arguments
+    ///
+    /// - room_name : The room name.
+    /// - excluded_user : The user left out, usually the author of a message.
+    /// - pool : The connection pool.
+    pub async fn find_followers(
+        room_name: &str,
+        excluded_user: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<i32>, sqlx::Error> {
+        sqlx::query_scalar("SELECT user_id FROM READ_MARKER WHERE room = $1 AND user_id != $2")
+            .bind(room_name)
+            .bind(excluded_user)
+            .fetch_all(pool)
+            .await
+    }
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl UnreadCount {
+    /// Returns the number of unread messages in each room the user follows.
+    ///
+    /// A user follows the rooms they read messages in, along with their
+    /// direct conversations, all of whose messages are unread until they open
+    /// them.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user whose counters are returned.
+    /// - pool : The connection pool.
+    pub async fn find_for_user(
+        user_id: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        // The rooms are first matched on their prefix, the regular rooms
+        // whose name merely looks like a conversation being then left out.
+        let direct_rooms: Vec<String> = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT room FROM MESSAGE WHERE room LIKE $1 OR room LIKE $2",
+        )
+        .bind(format!("{DM_ROOM_PREFIX}{user_id}-%"))
+        .bind(format!("{DM_ROOM_PREFIX}%-{user_id}"))
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .filter(|room| interlocutor_id(room, user_id).is_some())
+        .collect();
+        let mut counts: Vec<Self> = sqlx::query_as(
+            "WITH followed AS (
+                SELECT room, last_read_uuid, last_read_at FROM READ_MARKER WHERE user_id = $1
+                UNION
+                SELECT room, NULL::UUID, NULL::TIMESTAMPTZ FROM UNNEST($2::VARCHAR[]) AS d(room)
+                WHERE room NOT IN (SELECT room FROM READ_MARKER WHERE user_id = $1)
+            )
+            SELECT f.room, f.last_read_uuid AS last_read, COUNT(m.uuid) AS unread
+            FROM followed f
+            LEFT JOIN MESSAGE m ON m.room = f.room
+                AND m.author != $1
+                AND m.deleted_at IS NULL
+                AND m.timestamp > COALESCE(f.last_read_at, '-infinity')
+            GROUP BY f.room, f.last_read_uuid
+            ORDER BY f.room",
+        )
+        .bind(user_id)
+        .bind(&direct_rooms)
+        .fetch_all(pool)
+        .await?;
+        let interlocutor_ids: Vec<i32> = counts
+            .iter()
+            .filter_map(|count| interlocutor_id(&count.room, user_id))
+            .collect();
+        if !interlocutor_ids.is_empty() {
+            let interlocutors = PartialUser::find_by_ids(&interlocutor_ids, pool).await?;
+            for count in counts.iter_mut() {
+                let Some(id) = interlocutor_id(&count.room, user_id) else {
+                    continue;
+                };
+                count.interlocutor = interlocutors
+                    .iter()
+                    .find(|interlocutor| interlocutor.id == id)
+                    .map(|interlocutor| interlocutor.login.clone());
+            }
+        }
+        Ok(counts)
+    }
+}
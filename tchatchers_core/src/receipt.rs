@@ -0,0 +1,87 @@
+//! Receipts keep track of which users received and read a message.
+//!
+//! They are recorded per user, so that the author of a message knows who
+//! read it in rooms with several participants.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use chrono::{DateTime, Utc};
+
+#[cfg(any(feature = "back", feature = "cli"))]
+use uuid::Uuid;
+
+/// The reception of a message by one of the users of the room.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageReceipt {
+    /// The user who received the message.
+    pub user_id: i32,
+    /// The name of the user who received the message.
+    pub name: String,
+    /// When the message has been delivered to the user.
+    pub delivered_at: DateTime<Utc>,
+    /// When the user has seen the message, if they did.
+    pub seen_at: Option<DateTime<Utc>>,
+}
+
+/// Builds the expression aggregating the receipts of a message as JSON.
+///
+/// # Arguments
+///
+/// - message_uuid : The expression giving the identifier of the message.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub(crate) fn aggregate_receipts(message_uuid: &str) -> String {
+    format!(
+        "COALESCE((SELECT json_agg(json_build_object('userId', r.user_id, 'name', rc.name, 'deliveredAt', r.delivered_at, 'seenAt', r.seen_at) ORDER BY r.delivered_at)
+        FROM MESSAGE_RECEIPT r INNER JOIN CHATTER rc ON r.user_id = rc.id WHERE r.message_uuid = {message_uuid}), '[]'::JSON)"
+    )
+}
+
+/// The column containing the aggregated receipts of a message.
+#[cfg(any(feature = "back", feature = "cli"))]
+#[derive(sqlx::FromRow)]
+pub struct ReceiptsRow {
+    receipts: sqlx::types::Json<Vec<MessageReceipt>>,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl From<ReceiptsRow> for Vec<MessageReceipt> {
+    fn from(row: ReceiptsRow) -> Self {
+        row.receipts.0
+    }
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl MessageReceipt {
+    /// Records that a message has been delivered to the given users.
+    ///
+    /// Returns the receipts of the message.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message delivered.
+    /// - user_ids : The users the message has been delivered to.
+    /// - pool : The connection pool.
+    pub async fn mark_as_delivered(
+        message_uuid: Uuid,
+        user_ids: &[i32],
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO MESSAGE_RECEIPT(message_uuid, user_id)
+            SELECT $1, UNNEST($2::INTEGER[])
+            ON CONFLICT DO NOTHING",
+        )
+        .bind(message_uuid)
+        .bind(user_ids)
+        .execute(pool)
+        .await?;
+        let row: ReceiptsRow =
+            sqlx::query_as(&format!("SELECT {} AS receipts", aggregate_receipts("$1")))
+                .bind(message_uuid)
+                .fetch_one(pool)
+                .await?;
+        Ok(row.into())
+    }
+}
@@ -0,0 +1,189 @@
+//! Retention policies limit how long the messages of a room are kept.
+//!
+//! A policy can bound both the age of the messages and their number, the
+//! messages exceeding any of the limits being purged. The rooms without a
+//! policy of their own fall back on the default one, if defined.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+/// The key under which the default policy is stored.
+///
+/// It can't collide with a room name since these can only contain
+/// alphanumeric characters, dashes and underscores, as checked by
+/// `RoomNameValidator`.
+#[cfg(any(feature = "back", feature = "cli"))]
+const DEFAULT_POLICY_KEY: &str = "*";
+
+/// The messages exceeding the limits of the policy applied to their room,
+/// along with their room.
+///
+/// The rooms are listed first, so that the messages of each room are then
+/// looked up through the index on the room and the timestamp.
+#[cfg(any(feature = "back", feature = "cli"))]
+const EXPIRED_MESSAGES: &str = "WITH RECURSIVE rooms(room) AS (
+        (SELECT room FROM MESSAGE ORDER BY room LIMIT 1)
+        UNION ALL
+        SELECT (SELECT m.room FROM MESSAGE m WHERE m.room > r.room ORDER BY m.room LIMIT 1)
+        FROM rooms r WHERE r.room IS NOT NULL
+    )
+    SELECT e.uuid, r.room, p.room IS NULL AS is_default
+    FROM rooms r
+    CROSS JOIN LATERAL (
+        SELECT NULLIF(room, '*') AS room, max_age_days, max_messages FROM ROOM_RETENTION
+        WHERE room IN (r.room, '*')
+        ORDER BY room = '*'
+        LIMIT 1
+    ) p
+    CROSS JOIN LATERAL (
+        SELECT uuid FROM MESSAGE
+        WHERE room = r.room AND timestamp < CURRENT_TIMESTAMP - make_interval(days => p.max_age_days)
+        UNION
+        (SELECT uuid FROM MESSAGE
+        WHERE room = r.room AND p.max_messages IS NOT NULL
+        ORDER BY timestamp DESC, uuid DESC
+        OFFSET p.max_messages)
+    ) e
+    WHERE r.room IS NOT NULL";
+
+/// The limits applied to the messages of a room.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// The room the policy applies to, none for the default policy.
+    pub room: Option<String>,
+    /// The number of days after which the messages are purged, none if they
+    /// are kept whatever their age.
+    pub max_age_days: Option<i32>,
+    /// The number of most recent messages kept, none if they are kept
+    /// whatever their number.
+    pub max_messages: Option<i32>,
+}
+
+/// The number of messages a policy would purge in a room.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+pub struct RetentionReport {
+    /// The room the messages belong to.
+    pub room: String,
+    /// Whether the room falls back on the default policy.
+    pub is_default: bool,
+    /// The number of messages that would be purged.
+    pub expired_messages: i64,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl RetentionPolicy {
+    /// Creates or replaces a policy.
+    ///
+    /// # Arguments
+    ///
+    /// - pool : The connection pool.
+    pub async fn upsert(
+        &self,
+        pool: &sqlx::PgPool,
+    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO ROOM_RETENTION(room, max_age_days, max_messages) VALUES ($1, $2, $3)
+            ON CONFLICT(room) DO UPDATE SET max_age_days=$2, max_messages=$3, updated_at=CURRENT_TIMESTAMP",
+        )
+        .bind(self.room.as_deref().unwrap_or(DEFAULT_POLICY_KEY))
+        .bind(self.max_age_days)
+        .bind(self.max_messages)
+        .execute(pool)
+        .await
+    }
+
+    /// Removes a policy, the room then falling back on the default one.
+    ///
+    /// # Arguments
+    ///
+    /// - room_name : The room of the policy, none for the default policy.
+    /// - pool : The connection pool.
+    pub async fn delete(
+        room_name: Option<&str>,
+        pool: &sqlx::PgPool,
+    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+        sqlx::query("DELETE FROM ROOM_RETENTION WHERE room=$1")
+            .bind(room_name.unwrap_or(DEFAULT_POLICY_KEY))
+            .execute(pool)
+            .await
+    }
+
+    /// Returns the policy applied to a room, which is either its own or the
+    /// default one.
+    ///
+    /// # Arguments
+    ///
+    /// - room_name : The room name.
+    /// - pool : The connection pool.
+    pub async fn find_for_room(
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT NULLIF(room, '*') AS room, max_age_days, max_messages FROM ROOM_RETENTION
+            WHERE room IN ($1, '*')
+            ORDER BY room = '*'
+            LIMIT 1",
+        )
+        .bind(room_name)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Returns all the policies, the default one first.
+    ///
+    /// # Arguments
+    ///
+    /// - pool : The connection pool.
+    pub async fn find_all(pool: &sqlx::PgPool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT NULLIF(room, '*') AS room, max_age_days, max_messages FROM ROOM_RETENTION
+            ORDER BY room = '*' DESC, room",
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns, for each room, the number of messages the policies would
+    /// purge, without removing any.
+    ///
+    /// # Arguments
+    ///
+    /// - pool : The connection pool.
+    pub async fn dry_run(pool: &sqlx::PgPool) -> Result<Vec<RetentionReport>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT room, is_default, COUNT(*) AS expired_messages FROM ({EXPIRED_MESSAGES}) e
+            GROUP BY room, is_default
+            ORDER BY expired_messages DESC"
+        ))
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Purges a batch of the messages exceeding the limits of their room's
+    /// policy, and notifies their deletion.
+    ///
+    /// Returns the number of messages purged, the purge being over once it
+    /// is lower than the batch size.
+    ///
+    /// # Arguments
+    ///
+    /// - batch_size : The maximum number of messages purged.
+    /// - pool : The connection pool.
+    pub async fn purge_batch(batch_size: i64, pool: &sqlx::PgPool) -> Result<u64, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let purged: Vec<(uuid::Uuid, String)> = sqlx::query_as(&format!(
+            "DELETE FROM MESSAGE WHERE uuid IN (SELECT uuid FROM ({EXPIRED_MESSAGES}) e LIMIT $1)
+            RETURNING uuid, room"
+        ))
+        .bind(batch_size)
+        .fetch_all(&mut tx)
+        .await?;
+        crate::ws_message::notify_deletions(&purged, &mut tx).await?;
+        tx.commit().await?;
+        Ok(purged.len() as u64)
+    }
+}
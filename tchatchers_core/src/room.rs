@@ -7,6 +7,8 @@
 //! that reconnects retieve the messages sent before he joined.
 
 use crate::common::RE_LIMITED_CHARS;
+use crate::user::PartialUser;
+use chrono::{DateTime, Utc};
 use validator::Validate;
 
 #[derive(Debug, Validate)]
@@ -23,3 +25,35 @@ impl From<String> for RoomNameValidator {
         Self { name: value }
     }
 }
+
+/// A user currently connected to a room.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomMember {
+    /// The user's id.
+    pub user_id: i32,
+    /// The user's login.
+    pub login: String,
+    /// The user's name.
+    pub name: String,
+    /// The user's profile picture.
+    pub pfp: Option<String>,
+    /// The number of connections the user has opened on the room.
+    pub connections: u32,
+    /// When the user joined the room for the first time since they are
+    /// connected.
+    pub joined_at: DateTime<Utc>,
+}
+
+impl From<&PartialUser> for RoomMember {
+    fn from(user: &PartialUser) -> Self {
+        Self {
+            user_id: user.id,
+            login: user.login.clone(),
+            name: user.name.clone(),
+            pfp: user.pfp.clone(),
+            connections: 0,
+            joined_at: Utc::now(),
+        }
+    }
+}
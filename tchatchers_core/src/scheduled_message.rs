@@ -0,0 +1,221 @@
+//! Scheduled messages are composed by the users to be sent later on.
+//!
+//! They are kept apart from the other messages until they are due, the
+//! server then sending them as if their author just did.
+
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use crate::ws_message::{allowed_chars, text_length};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+/// The number of days within which a message can be scheduled.
+pub const MAX_SCHEDULE_DAYS: i64 = 30;
+
+/// The maximum number of messages a user can have waiting to be sent.
+pub const MAX_SCHEDULED_MESSAGES_PER_USER: i64 = 25;
+
+/// The number of seconds after which a message claimed by a server that
+/// didn't send it can be claimed again.
+pub const CLAIM_EXPIRY_SECONDS: i64 = 300;
+
+/// A message waiting to be sent.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledMessage {
+    /// The identifier the message will have once sent.
+    pub uuid: Uuid,
+    /// The author of the message.
+    pub author: i32,
+    /// The room the message will be sent in.
+    pub room: String,
+    /// The content of the message.
+    pub content: String,
+    /// The message this one replies to, if any.
+    pub reply_to: Option<Uuid>,
+    /// When the message has to be sent.
+    pub send_at: DateTime<Utc>,
+    /// When the message has been scheduled.
+    pub created_at: DateTime<Utc>,
+}
+
+/// Structure used only to schedule new messages.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct InsertableScheduledMessage {
+    /// The content of the message.
+    #[validate(
+        custom(function = "text_length", code = "content_length"),
+        custom(function = "allowed_chars", code = "forbidden_chars")
+    )]
+    pub content: String,
+    /// The message this one replies to, if any.
+    #[serde(default)]
+    pub reply_to: Option<Uuid>,
+    /// When the message has to be sent.
+    #[validate(custom(function = "schedulable", code = "send_at_out_of_range"))]
+    pub send_at: DateTime<Utc>,
+}
+
+fn schedulable(send_at: &DateTime<Utc>) -> Result<(), ValidationError> {
+    let now = Utc::now();
+    match *send_at > now && *send_at <= now + Duration::days(MAX_SCHEDULE_DAYS) {
+        true => Ok(()),
+        false => Err(ValidationError::new("send_at_out_of_range")),
+    }
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl InsertableScheduledMessage {
+    /// Schedules the message.
+    ///
+    /// Returns none if the author already has too many messages waiting to
+    /// be sent.
+    ///
+    /// # Arguments
+    ///
+    /// - author : The author of the message.
+    /// - room_name : The room the message will be sent in.
+    /// - pool : The connection pool.
+    pub async fn insert(
+        &self,
+        author: i32,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<Option<ScheduledMessage>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        // The messages of the author are counted one request at a time, so
+        // that concurrent requests can't exceed the limit.
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('SCHEDULED_MESSAGE'), $1)")
+            .bind(author)
+            .execute(&mut tx)
+            .await?;
+        let scheduled = sqlx::query_as(
+            "INSERT INTO SCHEDULED_MESSAGE(uuid, author, room, content, reply_to, send_at)
+            SELECT $1, $2, $3, $4, $5, $6
+            WHERE (SELECT COUNT(*) FROM SCHEDULED_MESSAGE WHERE author=$2) < $7
+            RETURNING *",
+        )
+        .bind(Uuid::new_v4())
+        .bind(author)
+        .bind(room_name)
+        .bind(&self.content)
+        .bind(self.reply_to)
+        .bind(self.send_at)
+        .bind(MAX_SCHEDULED_MESSAGES_PER_USER)
+        .fetch_optional(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(scheduled)
+    }
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl ScheduledMessage {
+    /// Returns the messages a user scheduled in a room, from the first to be
+    /// sent to the last.
+    ///
+    /// # Arguments
+    ///
+    /// - author : The author of the messages.
+    /// - room_name : The room the messages will be sent in.
+    /// - pool : The connection pool.
+    pub async fn find_for_author_in_room(
+        author: i32,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT * FROM SCHEDULED_MESSAGE WHERE author=$1 AND room=$2 ORDER BY send_at",
+        )
+        .bind(author)
+        .bind(room_name)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Cancels a scheduled message.
+    ///
+    /// Returns whether the message has been cancelled, it isn't if it has
+    /// already been sent, is being sent or belongs to another user.
+    ///
+    /// # Arguments
+    ///
+    /// - uuid : The message to cancel.
+    /// - author : The user cancelling the message.
+    /// - pool : The connection pool.
+    pub async fn cancel(uuid: Uuid, author: i32, pool: &sqlx::PgPool) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM SCHEDULED_MESSAGE WHERE uuid=$1 AND author=$2 AND claimed_at IS NULL",
+        )
+        .bind(uuid)
+        .bind(author)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Claims and returns the messages that are due.
+    ///
+    /// The messages being locked while claimed, each of them is only
+    /// returned once even if several servers fetch them concurrently. They
+    /// are kept until they are either deleted once sent or released, the
+    /// claims not settled within `CLAIM_EXPIRY_SECONDS` expiring.
+    ///
+    /// # Arguments
+    ///
+    /// - limit : The maximum number of messages returned.
+    /// - pool : The connection pool.
+    pub async fn claim_due(limit: i64, pool: &sqlx::PgPool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "UPDATE SCHEDULED_MESSAGE SET claimed_at=CURRENT_TIMESTAMP WHERE uuid IN (
+                SELECT uuid FROM SCHEDULED_MESSAGE WHERE send_at <= CURRENT_TIMESTAMP
+                AND (claimed_at IS NULL OR claimed_at < CURRENT_TIMESTAMP - make_interval(secs => $2))
+                ORDER BY send_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *",
+        )
+        .bind(limit)
+        .bind(CLAIM_EXPIRY_SECONDS as f64)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Releases a claimed message, so that it is sent again later on.
+    ///
+    /// # Arguments
+    ///
+    /// - uuid : The message to release.
+    /// - pool : The connection pool.
+    pub async fn release(
+        uuid: Uuid,
+        pool: &sqlx::PgPool,
+    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+        sqlx::query("UPDATE SCHEDULED_MESSAGE SET claimed_at=NULL WHERE uuid=$1")
+            .bind(uuid)
+            .execute(pool)
+            .await
+    }
+
+    /// Removes a claimed message, once it has been sent or definitively
+    /// refused.
+    ///
+    /// # Arguments
+    ///
+    /// - uuid : The message to remove.
+    /// - pool : The connection pool.
+    pub async fn delete(
+        uuid: Uuid,
+        pool: &sqlx::PgPool,
+    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+        sqlx::query("DELETE FROM SCHEDULED_MESSAGE WHERE uuid=$1")
+            .bind(uuid)
+            .execute(pool)
+            .await
+    }
+}
@@ -227,7 +227,7 @@ impl From<User> for PartialUser {
     }
 }
 
-#[cfg(feature = "cli")]
+#[cfg(any(feature = "back", feature = "cli"))]
 impl PartialUser {
     /// Find a user by ID in the database.
     ///
@@ -258,6 +258,21 @@ impl PartialUser {
             .await
     }
 
+    /// Find the users matching the given ids.
+    ///
+    /// The unknown ids are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// - ids : The ids of the users we are looking for.
+    /// - pool : The pool of connection.
+    pub async fn find_by_ids(ids: &[i32], pool: &PgPool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM CHATTER WHERE id = ANY($1)")
+            .bind(ids)
+            .fetch_all(pool)
+            .await
+    }
+
     /// Find a user from his name.
     ///
     /// This is an exact match look up.
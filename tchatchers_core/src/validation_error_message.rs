@@ -3,12 +3,14 @@
 
 use std::fmt::Display;
 
+use crate::ws_message::{MESSAGE_MAX_LENGTH, MESSAGE_MIN_LENGTH};
+
 #[cfg(feature = "back")]
 use axum::{http::StatusCode, response::IntoResponse};
 use validator::ValidationErrors;
 
 /// The error message struct.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct ValidationErrorMessage {
     /// The concerned error field.
     field: String,
@@ -28,6 +30,13 @@ impl From<ValidationErrors> for ValidationErrorMessage {
     }
 }
 
+impl ValidationErrorMessage {
+    /// The code of the rule that hasn't been respected.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+}
+
 impl Display for ValidationErrorMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.code.as_str() {
@@ -37,6 +46,10 @@ impl Display for ValidationErrorMessage {
                 self.field
             ),
             "limited_chars" => write!(f, "The {} doesn't respect the scope of chars allowed.\nOnly letters, numbers, dashes and underscores are allowed.", self.field),
+            "content_length" => write!(f, "A message must contain between {MESSAGE_MIN_LENGTH} and {MESSAGE_MAX_LENGTH} characters."),
+            "forbidden_chars" => write!(f, "The {} contains forbidden characters.", self.field),
+            "send_at_out_of_range" => write!(f, "A message can only be scheduled within the next 30 days."),
+            "invalid_pattern" => write!(f, "The pattern isn't a valid regular expression."),
             "security_constraints_not_matched" => write!(f, "The {} doesn't match the security constraints.\nIt is required to have at least one uppercase character, one lowercase character and one number in the {}.", self.field, self.field),
             _ => write!(f, "An error happened druing the validation of the form"),
         }
@@ -6,9 +6,46 @@
 // Copyright ⓒ 2022 LABEYE Loïc
 // This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
 
+use crate::attachment::Attachment;
+use crate::pin::PinnedMessage;
+use crate::protocol::Capability;
+use crate::reaction::MessageReaction;
+use crate::receipt::MessageReceipt;
+use crate::room::{RoomMember, RoomNameValidator};
 use crate::user::PartialUser;
+use crate::validation_error_message::ValidationErrorMessage;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+#[cfg(any(feature = "back", feature = "cli"))]
+use crate::{
+    attachment::{aggregate_attachments, AttachmentsRow},
+    direct_message::{can_access_room_condition, DM_ROOM_PREFIX},
+    profile::Profile,
+    reaction::{aggregate_reactions, ReactionsRow},
+    receipt::{aggregate_receipts, ReceiptsRow},
+};
+
+/// The number of messages returned by default when paginating a room.
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// The maximum number of messages that can be requested in a single page.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// The Postgres channel on which the message deletions are notified.
+pub const MESSAGES_DELETED_CHANNEL: &str = "messages_deleted";
+
+/// The maximum number of characters of a message kept in its preview.
+pub const PREVIEW_LENGTH: i32 = 64;
+
+/// The number of seconds after which a user that stopped sending typing
+/// notifications isn't considered as typing anymore.
+pub const TYPING_EXPIRY_SECONDS: u64 = 5;
+
+/// The minimum number of seconds between two typing notifications sent by
+/// the same client.
+pub const TYPING_REFRESH_SECONDS: u64 = 3;
 
 /// The types of messages shared between users.
 ///
@@ -18,6 +55,13 @@ use uuid::Uuid;
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub enum WsMessage {
+    /// Message opening a connection, sent first by the client with the
+    /// capabilities it supports, then by the server with the ones both sides
+    /// support.
+    Hello {
+        protocol_version: u16,
+        capabilities: Vec<Capability>,
+    },
     /// Content to be shared among all subscribers of the application.
     Send(WsMessageContent),
     /// Content to be displayed on client side.
@@ -33,8 +77,25 @@ pub enum WsMessage {
         messages: Vec<WsMessageContent>,
         session_id: Uuid,
     },
-    /// Indicates that the user has seeen the messages.
-    MessagesSeen(Vec<Uuid>),
+    /// Action sent by a client to retrieve a page of the room's history.
+    ///
+    /// When no cursor is given, the most recent messages of the room are
+    /// returned.
+    RetrieveMessagesPage {
+        session_id: Uuid,
+        before: Option<MessageCursor>,
+        page_size: i64,
+    },
+    /// Page of messages sent by the server in response to a
+    /// `RetrieveMessagesPage` request.
+    MessagesPageRetrieved { page: MessagePage, session_id: Uuid },
+    /// Indicates that a user has seeen the messages.
+    MessagesSeen {
+        messages: Vec<Uuid>,
+        user_id: i32,
+        name: String,
+        seen_at: DateTime<Utc>,
+    },
     /// Responds to Ping !
     Pong,
     /// Service !
@@ -57,6 +118,209 @@ pub enum WsMessage {
     ErrorOnMessage(String),
     /// Inform that one has seen the messages.
     Seen(Vec<Uuid>),
+    /// Error sent by the server to the client whose request couldn't be
+    /// processed.
+    Error(WsError),
+    /// Action sent by a client to replace the content of one of its messages.
+    Edit { uuid: Uuid, content: String },
+    /// Information sent by the server to inform a message has been edited.
+    Edited {
+        uuid: Uuid,
+        content: String,
+        edited_at: DateTime<Utc>,
+    },
+    /// Action sent by a client to delete messages.
+    ///
+    /// Users can only delete their own messages, unless they are moderators.
+    Delete(Vec<Uuid>),
+    /// Information sent by the server to inform messages have been deleted.
+    Deleted(Vec<Uuid>),
+    /// Action sent by a client to react to a message.
+    React { uuid: Uuid, emoji: String },
+    /// Action sent by a client to remove one of its reactions.
+    Unreact { uuid: Uuid, emoji: String },
+    /// Information sent by the server to inform the reactions on a message
+    /// changed.
+    ReactionsUpdated {
+        uuid: Uuid,
+        reactions: Vec<MessageReaction>,
+    },
+    /// Action sent by a client to inform the room its user is typing.
+    ///
+    /// This notification isn't persisted and expires if not renewed.
+    Typing,
+    /// Action sent by a client to inform the room its user stopped typing.
+    StoppedTyping,
+    /// Information sent by the server with the users currently typing in the
+    /// room.
+    UsersTyping(Vec<TypingUser>),
+    /// Information sent by the server when a user joins or leaves the room,
+    /// with the users currently connected.
+    PresenceChanged(Vec<RoomMember>),
+    /// Information sent by the server to a user mentioned in a message,
+    /// whatever the room they are connected to.
+    Mentioned(WsMessageContent),
+    /// Action sent by a client to pin a message in the room.
+    ///
+    /// Only the moderators and the owners of the room can pin messages.
+    Pin(Uuid),
+    /// Action sent by a client to unpin a message from the room.
+    Unpin(Uuid),
+    /// Information sent by the server when the pinned messages of the room
+    /// changed.
+    PinsUpdated(Vec<PinnedMessage>),
+    /// Information sent by the server to a client sending messages too fast.
+    ///
+    /// The message hasn't been sent, and no other one will be accepted before
+    /// the delay, in milliseconds, is over.
+    SlowDown { uuid: Uuid, retry_after_ms: u64 },
+    /// Information sent by the server to the author of a message once it has
+    /// been persisted.
+    ///
+    /// The clients send the messages again until they are acknowledged, the
+    /// server ignoring the ones it already persisted.
+    Ack {
+        uuid: Uuid,
+        server_timestamp: DateTime<Utc>,
+    },
+    /// Information sent by the server to a user when the number of messages
+    /// they haven't read in one of the rooms they follow changed.
+    ///
+    /// The delta is positive when messages are sent, and negative when the
+    /// user reads them.
+    UnreadChanged { room: String, delta: i64 },
+}
+
+impl WsMessage {
+    /// Returns the capability a client has to support to be sent this
+    /// message, if any.
+    pub fn required_capability(&self) -> Option<Capability> {
+        match self {
+            WsMessage::Mentioned(_) => Some(Capability::Mentions),
+            WsMessage::UnreadChanged { .. } => Some(Capability::UnreadCounters),
+            _ => None,
+        }
+    }
+}
+
+/// The errors the server can report to a client over the websocket.
+///
+/// These are only sent to the client that emitted the faulty message, never
+/// to the whole room.
+#[derive(
+    Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, derive_more::Display,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum WsError {
+    /// The message couldn't be saved by the server.
+    #[display(fmt = "Your message couldn't be sent, please try again later.")]
+    MessageNotPersisted(Uuid),
+    /// Some of the messages marked as seen don't belong to the room the user
+    /// is connected to.
+    #[display(fmt = "You can't mark messages from another room as seen.")]
+    MessagesNotVisible(Vec<Uuid>),
+    /// The message doesn't exist in the room or hasn't been written by the
+    /// user.
+    #[display(fmt = "You can only edit your own messages.")]
+    MessageNotEditable(Uuid),
+    /// Some of the messages don't exist in the room or can't be deleted by
+    /// the user.
+    #[display(fmt = "Some of the messages couldn't be deleted.")]
+    MessagesNotDeletable(Vec<Uuid>),
+    /// The message replied to doesn't exist in the room.
+    #[display(fmt = "The message you replied to can't be found in this room.")]
+    ReplyParentNotFound(Uuid),
+    /// The reaction isn't a valid emoji.
+    #[display(fmt = "Only emojis can be used to react to a message.")]
+    InvalidReaction(String),
+    /// The user isn't allowed to pin messages in the room.
+    #[display(fmt = "You aren't allowed to pin messages in this room.")]
+    PinNotAllowed(Uuid),
+    /// The message to pin doesn't exist in the room.
+    #[display(fmt = "The message to pin can't be found in this room.")]
+    PinnedMessageNotFound(Uuid),
+    /// The message matched a filter rule rejecting it.
+    #[display(fmt = "Your message contains words that aren't allowed.")]
+    MessageRejected(Uuid),
+    /// The message doesn't respect the validation rules.
+    #[display(fmt = "{}", _1)]
+    InvalidMessage(Uuid, ValidationErrorMessage),
+    /// More files are joined to the message than allowed.
+    #[display(fmt = "Too many files are attached to the message.")]
+    TooManyAttachments(Uuid),
+    /// Some of the files joined to the message can't be found, or have been
+    /// uploaded by another user.
+    #[display(fmt = "Some of the files attached to the message can't be found.")]
+    UnknownAttachments(Uuid),
+}
+
+impl WsError {
+    /// Returns the label used to translate the error on client side.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MessageNotPersisted(_) => "ws_error_message_not_persisted",
+            Self::MessagesNotVisible(_) => "ws_error_messages_not_visible",
+            Self::MessageNotEditable(_) => "ws_error_message_not_editable",
+            Self::MessagesNotDeletable(_) => "ws_error_messages_not_deletable",
+            Self::ReplyParentNotFound(_) => "ws_error_reply_parent_not_found",
+            Self::InvalidReaction(_) => "ws_error_invalid_reaction",
+            Self::PinNotAllowed(_) => "ws_error_pin_not_allowed",
+            Self::PinnedMessageNotFound(_) => "ws_error_pinned_message_not_found",
+            Self::MessageRejected(_) => "ws_error_message_rejected",
+            Self::InvalidMessage(_, error) => match error.code() {
+                "content_length" => "ws_error_message_length",
+                "forbidden_chars" => "ws_error_message_forbidden_chars",
+                _ => "ws_error_invalid_message",
+            },
+            Self::TooManyAttachments(_) => "ws_error_too_many_attachments",
+            Self::UnknownAttachments(_) => "ws_error_unknown_attachments",
+        }
+    }
+
+    /// Fills the translation of the error's label with the limits it refers
+    /// to, so that these are only defined once.
+    ///
+    /// # Arguments
+    ///
+    /// - translation : The translation of the error's label.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tchatchers_core::ws_message::WsError;
+    /// use uuid::Uuid;
+    /// use validator::{ValidationError, ValidationErrors};
+    ///
+    /// let mut errors = ValidationErrors::new();
+    /// errors.add("content", ValidationError::new("content_length"));
+    /// let error = WsError::InvalidMessage(Uuid::nil(), errors.into());
+    /// assert_eq!(
+    ///     error.fill_translation("Entre {min} et {max} caractères."),
+    ///     "Entre 2 et 127 caractères."
+    /// );
+    /// ```
+    pub fn fill_translation(&self, translation: &str) -> String {
+        match self {
+            Self::InvalidMessage(_, error) if error.code() == "content_length" => translation
+                .replace("{min}", &MESSAGE_MIN_LENGTH.to_string())
+                .replace("{max}", &MESSAGE_MAX_LENGTH.to_string()),
+            _ => translation.to_string(),
+        }
+    }
+
+    /// Returns the message sent by the client that has been refused, if the
+    /// error is about one.
+    pub fn refused_message(&self) -> Option<Uuid> {
+        match self {
+            Self::MessageNotPersisted(uuid)
+            | Self::ReplyParentNotFound(uuid)
+            | Self::MessageRejected(uuid)
+            | Self::InvalidMessage(uuid, _)
+            | Self::TooManyAttachments(uuid)
+            | Self::UnknownAttachments(uuid) => Some(*uuid),
+            _ => None,
+        }
+    }
 }
 
 #[derive(
@@ -77,22 +341,44 @@ pub enum WsReceptionStatus {
     #[default]
     NotSent = 1,
     Sent = 2,
+    /// The message has been seen, only set on the messages sent before the
+    /// readers were recorded through the receipts.
     Seen = 3,
+    /// The message has been delivered to users connected to the room, but
+    /// not seen yet.
+    Delivered = 4,
 }
 
+/// The minimum number of characters of a message, unless files are joined to
+/// it.
+pub const MESSAGE_MIN_LENGTH: usize = 2;
+
+/// The maximum number of characters of a message.
+pub const MESSAGE_MAX_LENGTH: usize = 127;
+
 /// Standard used to communicate inside WS between the client and the server
 /// applications.
 #[derive(
-    Debug, Clone, serde::Serialize, serde::Deserialize, derivative::Derivative, PartialEq, Eq, Hash,
+    Debug,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    derivative::Derivative,
+    PartialEq,
+    Eq,
+    Hash,
+    Validate,
 )]
 #[derivative(Default)]
 #[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
 #[serde(rename_all = "camelCase")]
+#[validate(schema(function = "content_length"))]
 pub struct WsMessageContent {
     /// The message identifier, must be unique.
     #[derivative(Default(value = "Uuid::new_v4()"))]
     pub uuid: Uuid,
     /// The content of the message.
+    #[validate(custom(function = "allowed_chars", code = "forbidden_chars"))]
     pub content: String,
     /// The author of the message.
     ///
@@ -103,61 +389,603 @@ pub struct WsMessageContent {
     #[derivative(Default(value = "chrono::offset::Utc::now()"))]
     pub timestamp: DateTime<Utc>,
     /// The room on which the message has been emitted.
+    #[validate(custom = "room_name")]
     pub room: String,
     /// Whether a message has been received or not.
     pub reception_status: WsReceptionStatus,
+    /// When the message has been edited for the last time, if ever.
+    #[serde(default)]
+    pub edited_at: Option<DateTime<Utc>>,
+    /// When the message has been deleted, if ever.
+    ///
+    /// The content of a deleted message is never sent to the clients.
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// The message this one replies to, if any.
+    #[serde(default)]
+    pub reply_to: Option<Uuid>,
+    /// A preview of the message replied to, set by the server.
+    #[serde(default)]
+    #[cfg_attr(
+        any(feature = "back", feature = "cli"),
+        sqlx(flatten, try_from = "MessagePreviewRow")
+    )]
+    pub reply_preview: Option<MessagePreview>,
+    /// The reactions made on the message, grouped by emoji.
+    #[serde(default)]
+    #[cfg_attr(
+        any(feature = "back", feature = "cli"),
+        sqlx(flatten, try_from = "ReactionsRow")
+    )]
+    pub reactions: Vec<MessageReaction>,
+    /// The receptions of the message by the other users of the room.
+    #[serde(default)]
+    #[cfg_attr(
+        any(feature = "back", feature = "cli"),
+        sqlx(flatten, try_from = "ReceiptsRow")
+    )]
+    pub receipts: Vec<MessageReceipt>,
+    /// The files joined to the message.
+    #[serde(default)]
+    #[cfg_attr(
+        any(feature = "back", feature = "cli"),
+        sqlx(flatten, try_from = "AttachmentsRow")
+    )]
+    pub attachments: Vec<Attachment>,
+}
+
+/// Whether the content contains characters that can't be part of a message.
+///
+/// These are the control characters, besides the line feeds and the tabs, and
+/// the bidirectional overrides, which can be used to disguise the content.
+///
+/// # Arguments
+///
+/// - content : The content of the message.
+///
+/// # Examples
+///
+/// ```
+/// use tchatchers_core::ws_message::contains_forbidden_chars;
+///
+/// assert!(!contains_forbidden_chars("Hello\tworld !"));
+/// assert!(contains_forbidden_chars("Hello\u{7}"));
+/// assert!(contains_forbidden_chars("\u{202E}dlrow olleH"));
+/// ```
+pub fn contains_forbidden_chars(content: &str) -> bool {
+    content.chars().any(|c| {
+        (c.is_control() && c != '\n' && c != '\t')
+            || matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+    })
+}
+
+pub(crate) fn allowed_chars(content: &str) -> Result<(), ValidationError> {
+    match contains_forbidden_chars(content) {
+        true => Err(ValidationError::new("forbidden_chars")),
+        false => Ok(()),
+    }
+}
+
+fn room_name(room: &str) -> Result<(), ValidationError> {
+    RoomNameValidator::from(room.to_string())
+        .validate()
+        .map_err(|errors| {
+            errors
+                .field_errors()
+                .into_values()
+                .flat_map(|errors| errors.iter())
+                .next()
+                .cloned()
+                .unwrap_or_else(|| ValidationError::new("room_name"))
+        })
+}
+
+/// A message can only be empty when files are joined to it.
+fn content_length(message: &WsMessageContent) -> Result<(), ValidationError> {
+    match message.content.is_empty() && !message.attachments.is_empty() {
+        true => Ok(()),
+        false => text_length(&message.content),
+    }
+}
+
+/// The number of characters of a message has to be within the length limits.
+pub(crate) fn text_length(content: &str) -> Result<(), ValidationError> {
+    match (MESSAGE_MIN_LENGTH..=MESSAGE_MAX_LENGTH).contains(&content.chars().count()) {
+        true => Ok(()),
+        false => Err(ValidationError::new("content_length")),
+    }
+}
+
+/// A compact version of a message, displayed above the replies made to it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePreview {
+    /// The identifier of the message.
+    pub uuid: Uuid,
+    /// The name of the message's author.
+    pub author_name: String,
+    /// The beginning of the message's content, empty if it has been deleted.
+    pub content: String,
+    /// Whether the message has been deleted.
+    pub is_deleted: bool,
+}
+
+/// The columns describing the message replied to, when joined to a message.
+#[cfg(any(feature = "back", feature = "cli"))]
+#[derive(sqlx::FromRow)]
+pub struct MessagePreviewRow {
+    parent_uuid: Option<Uuid>,
+    parent_author_name: Option<String>,
+    parent_content: Option<String>,
+    parent_deleted_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl From<MessagePreviewRow> for Option<MessagePreview> {
+    fn from(row: MessagePreviewRow) -> Self {
+        let is_deleted = row.parent_deleted_at.is_some();
+        Some(MessagePreview {
+            uuid: row.parent_uuid?,
+            author_name: row.parent_author_name.unwrap_or_default(),
+            content: match is_deleted {
+                true => String::new(),
+                false => row.parent_content.unwrap_or_default(),
+            },
+            is_deleted,
+        })
+    }
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl MessagePreview {
+    /// Returns the preview of a message, if it exists in the given room.
+    ///
+    /// # Arguments
+    ///
+    /// - uuid : The message identifier.
+    /// - room_name : The room the message has to belong to.
+    /// - pool : The connection pool.
+    pub async fn find_in_room(
+        uuid: Uuid,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT m.uuid, c.name AS author_name,
+            CASE WHEN m.deleted_at IS NULL THEN LEFT(m.content, $3) ELSE '' END AS content,
+            m.deleted_at IS NOT NULL AS is_deleted
+            FROM MESSAGE m INNER JOIN CHATTER c ON m.author = c.id
+            WHERE m.uuid=$1 AND m.room=$2",
+        )
+        .bind(uuid)
+        .bind(room_name)
+        .bind(PREVIEW_LENGTH)
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+/// The messages deleted within a room, as notified by the database.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+pub struct MessagesDeletion {
+    /// The room the messages belonged to.
+    pub room: String,
+    /// The deleted messages.
+    pub messages: Vec<Uuid>,
+}
+
+/// A user currently typing a message in a room.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct TypingUser {
+    /// The user's id.
+    pub user_id: i32,
+    /// The name displayed to the other users.
+    pub name: String,
+}
+
+/// A previous version of an edited message.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(any(feature = "back", feature = "cli"), derive(sqlx::FromRow))]
+#[serde(rename_all = "camelCase")]
+pub struct MessageRevision {
+    /// The content of the message before it got edited.
+    pub content: String,
+    /// When this content has been written.
+    pub revised_at: DateTime<Utc>,
+}
+
+#[cfg(any(feature = "back", feature = "cli"))]
+impl MessageRevision {
+    /// Returns the previous versions of a message, from the most recent to the
+    /// oldest.
+    ///
+    /// # Arguments
+    ///
+    /// - message_uuid : The message the revisions are looked up for.
+    /// - pool : The connection pool.
+    pub async fn find_for_message(
+        message_uuid: Uuid,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT r.content, r.revised_at FROM MESSAGE_REVISION r INNER JOIN MESSAGE m ON r.message_uuid = m.uuid
+            WHERE r.message_uuid=$1 AND m.deleted_at IS NULL ORDER BY r.revised_at DESC",
+        )
+        .bind(message_uuid)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// A position in the history of a room, from which older messages can be
+/// fetched.
+///
+/// Messages are sorted by timestamp first, and then by uuid so that two
+/// messages emitted at the same time are never skipped.
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageCursor {
+    /// The timestamp of the oldest message known by the client.
+    pub timestamp: DateTime<Utc>,
+    /// The uuid of the oldest message known by the client.
+    pub uuid: Uuid,
+}
+
+impl From<&WsMessageContent> for MessageCursor {
+    fn from(message: &WsMessageContent) -> Self {
+        Self {
+            timestamp: message.timestamp,
+            uuid: message.uuid,
+        }
+    }
+}
+
+/// A page of messages retrieved from the history of a room.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePage {
+    /// The messages of the page, from the most recent to the oldest.
+    pub messages: Vec<WsMessageContent>,
+    /// Whether older messages can still be fetched after this page.
+    pub has_more: bool,
+}
+
+/// The criteria used to search for messages.
+///
+/// The text is matched against the content of the messages using the
+/// language of their author.
+#[derive(
+    Debug, Clone, Default, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Validate,
+)]
+pub struct MessageSearchQuery {
+    /// The text searched.
+    #[validate(length(min = 1, max = 127))]
+    pub q: String,
+    /// The room the messages have to belong to.
+    pub room: Option<String>,
+    /// The login of the author of the messages.
+    pub author: Option<String>,
+    /// The date before which the messages have been sent.
+    pub before: Option<DateTime<Utc>>,
+    /// The date after which the messages have been sent.
+    pub after: Option<DateTime<Utc>>,
+}
+
+/// Builds the statement used to retrieve the messages along with their author,
+/// the preview of the message they reply to, their reactions and their
+/// receipts.
+///
+/// # Arguments
+///
+/// - clauses : The clauses appended to the statement, the message table being aliased as `m`.
+#[cfg(any(feature = "back", feature = "cli"))]
+fn select_messages(clauses: &str) -> String {
+    format!(
+        "SELECT m.*, c.*,
+        p.uuid AS parent_uuid, pc.name AS parent_author_name,
+        LEFT(p.content, {PREVIEW_LENGTH}) AS parent_content, p.deleted_at AS parent_deleted_at,
+        {reactions} AS reactions, {receipts} AS receipts, {attachments} AS attachments
+        FROM MESSAGE m INNER JOIN CHATTER c ON m.author = c.id
+        LEFT JOIN MESSAGE p ON m.reply_to = p.uuid
+        LEFT JOIN CHATTER pc ON p.author = pc.id
+        {clauses}",
+        reactions = aggregate_reactions("m.uuid"),
+        receipts = aggregate_receipts("m.uuid"),
+        attachments = aggregate_attachments("m.uuid")
+    )
+}
+
+/// The result of the persistence of a message.
+#[cfg(any(feature = "back", feature = "cli"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistOutcome {
+    /// The message has been saved.
+    Created,
+    /// The message had already been saved by the same author at the given
+    /// timestamp, usually because the client sent it again after losing the
+    /// connection.
+    AlreadyPersisted(DateTime<Utc>),
+    /// The identifier is already used by a message of another user.
+    Conflict,
 }
 
 #[cfg(any(feature = "back", feature = "cli"))]
 impl WsMessageContent {
+    /// Removes the content of the message if it has been deleted.
+    fn redact_if_deleted(mut self) -> Self {
+        if self.deleted_at.is_some() {
+            self.content.clear();
+        }
+        self
+    }
+
     /// Returns the first 100 messages for a given room name.
     ///
     /// # Arguments
     ///
     /// - room_name : The room the query is made for.
     pub async fn query_all_for_room(room_name: &str, pool: &sqlx::PgPool) -> Vec<Self> {
-        sqlx::query_as("SELECT * FROM MESSAGE m INNER JOIN CHATTER c ON m.author = c.id WHERE room=$1 ORDER BY timestamp DESC LIMIT 100 ")
-            .bind(room_name)
-            .fetch_all(pool)
-            .await
-            .unwrap()
+        let messages: Vec<Self> = sqlx::query_as(&select_messages(
+            "WHERE m.room=$1 ORDER BY m.timestamp DESC LIMIT 100",
+        ))
+        .bind(room_name)
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        messages.into_iter().map(Self::redact_if_deleted).collect()
+    }
+
+    /// Returns a page of messages for a given room name, from the most recent
+    /// to the oldest.
+    ///
+    /// One more message than requested is fetched to know whether older
+    /// messages remain after the page.
+    ///
+    /// # Arguments
+    ///
+    /// - room_name : The room the query is made for.
+    /// - before : The cursor before which the messages are returned, the most recent messages are returned if none is given.
+    /// - page_size : The number of messages requested, bounded by `MAX_PAGE_SIZE`.
+    /// - pool : The connection pool.
+    pub async fn query_page_for_room(
+        room_name: &str,
+        before: Option<MessageCursor>,
+        page_size: i64,
+        pool: &sqlx::PgPool,
+    ) -> Result<MessagePage, sqlx::Error> {
+        let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        let mut messages: Vec<Self> = sqlx::query_as(&select_messages(
+            "WHERE m.room=$1 AND ($2::TIMESTAMPTZ IS NULL OR (m.timestamp, m.uuid) < ($2, $3))
+            ORDER BY m.timestamp DESC, m.uuid DESC LIMIT $4",
+        ))
+        .bind(room_name)
+        .bind(before.map(|cursor| cursor.timestamp))
+        .bind(before.map(|cursor| cursor.uuid))
+        .bind(page_size + 1)
+        .fetch_all(pool)
+        .await?;
+        let has_more = messages.len() as i64 > page_size;
+        messages.truncate(page_size as usize);
+        let messages = messages.into_iter().map(Self::redact_if_deleted).collect();
+        Ok(MessagePage { messages, has_more })
+    }
+
+    /// Returns the most recent message of each direct conversation the user
+    /// may take part in, from the most recent to the oldest.
+    ///
+    /// The rooms are only matched on their name, the caller is expected to
+    /// check that the user actually participates in them.
+    ///
+    /// # Arguments
+    ///
+    /// - user_id : The user whose conversations are looked up.
+    /// - pool : The connection pool.
+    pub async fn query_last_direct_messages(
+        user_id: i32,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let messages: Vec<Self> = sqlx::query_as(&select_messages(
+            "WHERE m.uuid IN (
+                SELECT DISTINCT ON (room) uuid FROM MESSAGE
+                WHERE room LIKE $1 OR room LIKE $2
+                ORDER BY room, timestamp DESC
+            )
+            ORDER BY m.timestamp DESC",
+        ))
+        .bind(format!("{DM_ROOM_PREFIX}{user_id}-%"))
+        .bind(format!("{DM_ROOM_PREFIX}%-{user_id}"))
+        .fetch_all(pool)
+        .await?;
+        Ok(messages.into_iter().map(Self::redact_if_deleted).collect())
+    }
+
+    /// Searches for the messages matching the given criteria, from the most
+    /// recent to the oldest.
+    ///
+    /// Deleted messages are never returned.
+    ///
+    /// # Arguments
+    ///
+    /// - query : The search criteria.
+    /// - user_id : The user searching, whose search is limited to the rooms they can access, none for operators.
+    /// - pool : The connection pool.
+    pub async fn search(
+        query: &MessageSearchQuery,
+        user_id: Option<i32>,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(&select_messages(&format!(
+            "WHERE m.deleted_at IS NULL
+            AND to_tsvector(m.search_config, m.content) @@ websearch_to_tsquery(m.search_config, $1)
+            AND ($2::VARCHAR IS NULL OR m.room = $2)
+            AND ($3::VARCHAR IS NULL OR c.login = $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR m.timestamp < $4)
+            AND ($5::TIMESTAMPTZ IS NULL OR m.timestamp > $5)
+            AND ($6::INTEGER IS NULL OR {})
+            ORDER BY m.timestamp DESC, m.uuid DESC LIMIT $7",
+            can_access_room_condition("m.room", "$6")
+        )))
+        .bind(&query.q)
+        .bind(&query.room)
+        .bind(&query.author)
+        .bind(query.before)
+        .bind(query.after)
+        .bind(user_id)
+        .bind(MAX_PAGE_SIZE)
+        .fetch_all(pool)
+        .await
     }
 
     /// Insert the message in the database.
     ///
+    /// Saving a message whose identifier is already used doesn't fail, so that
+    /// the clients can safely send a message again.
+    ///
     /// # Arguments
     ///
     /// - pool : the connection pool.
-    pub async fn persist(
-        &self,
+    pub async fn persist(&self, pool: &sqlx::PgPool) -> Result<PersistOutcome, sqlx::Error> {
+        let persisted: Option<(bool, DateTime<Utc>)> = sqlx::query_as(
+            "WITH inserted AS (
+                INSERT INTO MESSAGE(uuid, content, author, timestamp, room, reception_status, reply_to) VALUES ($1,$2,$3,$4,$5,$6,$7)
+                ON CONFLICT (uuid) DO NOTHING
+                RETURNING timestamp
+            )
+            SELECT TRUE, timestamp FROM inserted
+            UNION ALL
+            SELECT FALSE, timestamp FROM MESSAGE
+            WHERE uuid = $1 AND author = $3 AND NOT EXISTS (SELECT 1 FROM inserted)",
+        )
+        .bind(self.uuid)
+        .bind(&self.content)
+        .bind(self.author.id)
+        .bind(self.timestamp)
+        .bind(&self.room)
+        .bind(self.reception_status)
+        .bind(self.reply_to)
+        .fetch_optional(pool)
+        .await?;
+        Ok(match persisted {
+            Some((true, _)) => PersistOutcome::Created,
+            Some((false, timestamp)) => PersistOutcome::AlreadyPersisted(timestamp),
+            None => PersistOutcome::Conflict,
+        })
+    }
+
+    /// Replaces the content of a message, keeping its previous content as a
+    /// revision.
+    ///
+    /// Returns the edition timestamp, or nothing if the message doesn't exist
+    /// in the room or hasn't been written by the given author.
+    ///
+    /// # Arguments
+    ///
+    /// - uuid : The message to edit.
+    /// - content : The new content of the message.
+    /// - author_id : The user requesting the edition.
+    /// - room_name : The room the message belongs to.
+    /// - pool : The connection pool.
+    pub async fn edit(
+        uuid: Uuid,
+        content: &str,
+        author_id: i32,
+        room_name: &str,
         pool: &sqlx::PgPool,
-    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
-        sqlx::query("INSERT INTO MESSAGE(uuid, content, author, timestamp, room, reception_status) VALUES ($1,$2,$3,$4,$5,$6)")
-            .bind(self.uuid)
-            .bind(&self.content)
-            .bind(self.author.id)
-            .bind(self.timestamp)
-            .bind(&self.room)
-            .bind(self.reception_status)
-            .execute(pool)
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar(
+            "WITH previous AS (
+                SELECT uuid, content, COALESCE(edited_at, timestamp) AS revised_at FROM MESSAGE
+                WHERE uuid=$1 AND author=$2 AND room=$3 AND deleted_at IS NULL
+                FOR UPDATE
+            ), revision AS (
+                INSERT INTO MESSAGE_REVISION(message_uuid, content, revised_at)
+                SELECT uuid, content, revised_at FROM previous
+            )
+            UPDATE MESSAGE m SET content=$4, edited_at=CURRENT_TIMESTAMP
+            FROM previous p WHERE m.uuid = p.uuid
+            RETURNING m.edited_at",
+        )
+        .bind(uuid)
+        .bind(author_id)
+        .bind(room_name)
+        .bind(content)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Returns the room a message has been sent in, if it exists and hasn't
+    /// been deleted.
+    ///
+    /// # Arguments
+    ///
+    /// - uuid : the message identifier.
+    /// - pool : the connection pool.
+    pub async fn find_room(uuid: Uuid, pool: &sqlx::PgPool) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT room FROM MESSAGE WHERE uuid=$1 AND deleted_at IS NULL")
+            .bind(uuid)
+            .fetch_optional(pool)
             .await
     }
 
-    /// Mark a list of existing messages as seen?
+    /// Returns the messages of the list that can't be found within the given
+    /// room.
+    ///
+    /// A user connected to a room can only see the messages emitted in it.
+    ///
+    /// # Arguments
+    ///
+    /// - messages_uuid : the list of messages to check.
+    /// - room_name : the room the user is connected to.
+    /// - pool : the connection pool.
+    pub async fn find_not_in_room(
+        messages_uuid: &Vec<Uuid>,
+        room_name: &str,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT u.uuid FROM UNNEST($1::UUID[]) AS u(uuid)
+            WHERE NOT EXISTS (SELECT 1 FROM MESSAGE m WHERE m.uuid = u.uuid AND m.room = $2)",
+        )
+        .bind(messages_uuid)
+        .bind(room_name)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Mark a list of existing messages of a room as seen by a user.
+    ///
+    /// A receipt is recorded for the user, the messages written by the user
+    /// themselves are ignored. The reception status of the messages is left
+    /// as is, the readers being only known through the receipts.
+    ///
+    /// Returns the messages the user hadn't seen yet.
     ///
     /// # Arguments
     ///
     /// - messages_uuid : the list of messages seen.
+    /// - user_id : the user who has seen the messages.
+    /// - room_name : the room the messages belong to.
     /// - pool : the connection pool.
     pub async fn mark_as_seen(
         messages_uuid: &Vec<Uuid>,
+        user_id: i32,
+        room_name: &str,
         pool: &sqlx::PgPool,
-    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
-        sqlx::query("UPDATE MESSAGE SET reception_status=$1 WHERE uuid = ANY($2)")
-            .bind(WsReceptionStatus::Seen)
-            .bind(messages_uuid)
-            .execute(pool)
-            .await
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "INSERT INTO MESSAGE_RECEIPT(message_uuid, user_id, seen_at)
+            SELECT uuid, $2, CURRENT_TIMESTAMP FROM MESSAGE WHERE uuid = ANY($1) AND room = $3 AND author != $2
+            ON CONFLICT (message_uuid, user_id) DO UPDATE SET seen_at = EXCLUDED.seen_at
+            WHERE MESSAGE_RECEIPT.seen_at IS NULL
+            RETURNING message_uuid",
+        )
+        .bind(messages_uuid)
+        .bind(user_id)
+        .bind(room_name)
+        .fetch_all(pool)
+        .await
     }
 
     /// Deletes the message present in a room.
@@ -175,15 +1003,89 @@ impl WsMessageContent {
             .await
     }
 
-    pub async fn delete_messages(
+    /// Marks messages as deleted, and notifies the deletion on the
+    /// `MESSAGES_DELETED_CHANNEL` so that the connected clients are informed.
+    ///
+    /// Returns the messages that have been deleted.
+    ///
+    /// # Arguments
+    ///
+    /// - messages_uuid : The messages to delete.
+    /// - deleted_by : The user requesting the deletion, none if run by an operator. Users can only delete their own messages, unless they are moderators.
+    /// - room_name : The room the messages have to belong to, if any.
+    /// - pool : The connection pool.
+    pub async fn soft_delete(
         messages_uuid: &Vec<Uuid>,
+        deleted_by: Option<&PartialUser>,
+        room_name: Option<&str>,
         pool: &sqlx::PgPool,
-    ) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
-        sqlx::query("DELETE FROM MESSAGE WHERE uuid = ANY($1)")
-            .bind(messages_uuid)
-            .execute(pool)
-            .await
+    ) -> Result<Vec<Uuid>, sqlx::Error> {
+        let can_delete_any = match deleted_by {
+            Some(user) => user.profile >= Profile::Moderator,
+            None => true,
+        };
+        let mut tx = pool.begin().await?;
+        let deleted: Vec<(Uuid, String)> = sqlx::query_as(
+            "UPDATE MESSAGE SET deleted_at=CURRENT_TIMESTAMP, deleted_by=$2
+            WHERE uuid = ANY($1) AND deleted_at IS NULL
+            AND ($3::VARCHAR IS NULL OR room = $3)
+            AND ($4 OR author = $2)
+            RETURNING uuid, room",
+        )
+        .bind(messages_uuid)
+        .bind(deleted_by.map(|user| user.id))
+        .bind(room_name)
+        .bind(can_delete_any)
+        .fetch_all(&mut tx)
+        .await?;
+
+        // The files are removed from the disk once their attachment is gone.
+        sqlx::query("DELETE FROM ATTACHMENT WHERE message_uuid = ANY($1)")
+            .bind(deleted.iter().map(|(uuid, _)| *uuid).collect::<Vec<Uuid>>())
+            .execute(&mut tx)
+            .await?;
+
+        notify_deletions(&deleted, &mut tx).await?;
+        tx.commit().await?;
+        Ok(deleted.into_iter().map(|(uuid, _)| uuid).collect())
+    }
+}
+
+/// Notifies the deletion of messages on the `MESSAGES_DELETED_CHANNEL`, one
+/// notification being sent per room.
+///
+/// The notifications are only delivered once the transaction is committed.
+///
+/// # Arguments
+///
+/// - deleted : The messages deleted, along with their room.
+/// - tx : The transaction the messages have been deleted in.
+#[cfg(any(feature = "back", feature = "cli"))]
+pub(crate) async fn notify_deletions(
+    deleted: &[(Uuid, String)],
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), sqlx::Error> {
+    let mut deletions: Vec<MessagesDeletion> = vec![];
+    for (uuid, room) in deleted {
+        match deletions.iter_mut().find(|deletion| &deletion.room == room) {
+            Some(deletion) => deletion.messages.push(*uuid),
+            None => deletions.push(MessagesDeletion {
+                room: room.clone(),
+                messages: vec![*uuid],
+            }),
+        }
+    }
+    for deletion in deletions {
+        sqlx::query(
+            "SELECT pg_notify($1, json_build_object('room', $2::VARCHAR, 'messages', $3::UUID[])::TEXT)",
+        )
+        .bind(MESSAGES_DELETED_CHANNEL)
+        .bind(deletion.room)
+        .bind(deletion.messages)
+        .execute(&mut *tx)
+        .await?;
     }
+    Ok(())
 }
 
 /// A struct used for the activity reports.
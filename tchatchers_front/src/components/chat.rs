@@ -1,12 +1,50 @@
 // Copyright ⓒ 2022 LABEYE Loïc
 // This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+use std::rc::Rc;
+
+use crate::utils::client_context::ClientContext;
+use crate::utils::markdown::render_markdown;
+use crate::utils::requester::Requester;
 use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use tchatchers_core::attachment::Attachment;
+use tchatchers_core::mention::mentioned_logins;
+use tchatchers_core::pin::can_pin;
+use tchatchers_core::profile::Profile;
+use tchatchers_core::reaction::{MessageReaction, DEFAULT_REACTIONS};
+use tchatchers_core::receipt::MessageReceipt;
 use tchatchers_core::user::PartialUser;
-use tchatchers_core::ws_message::{WsMessageContent, WsReceptionStatus};
+use tchatchers_core::ws_message::{
+    MessagePreview, MessageRevision, WsMessage, WsMessageContent, WsReceptionStatus, PREVIEW_LENGTH,
+};
 use uuid::Uuid;
-use yew::{function_component, html, use_state, AttrValue, Component, Context, Html, Properties};
+use web_sys::{Blob, HtmlInputElement, MouseEvent, SubmitEvent, Url};
+use yew::{
+    function_component, html, use_context, use_effect_with_deps, use_mut_ref, use_node_ref,
+    use_state, AttrValue, Callback, Component, Context, Html, Properties,
+};
+
+pub(crate) const DEFAULT_PFP: &str = "/assets/no_pfp.webp";
 
-const DEFAULT_PFP: &str = "/assets/no_pfp.webp";
+/// Formats a file size in a human readable way.
+pub(crate) fn format_file_size(size: i64) -> String {
+    match size {
+        size if size < 1024 => format!("{size} B"),
+        size if size < 1024 * 1024 => format!("{:.1} KB", size as f64 / 1024.0),
+        size => format!("{:.1} MB", size as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+/// Formats a timestamp the way it is displayed in the chat.
+pub(crate) fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    format!(
+        "on {:02}/{:02}/{} at {}:{:02}",
+        timestamp.day(),
+        timestamp.month(),
+        timestamp.year(),
+        timestamp.hour(),
+        timestamp.minute()
+    )
+}
 
 #[derive(Properties, PartialEq)]
 struct ProfilePictureProperties {
@@ -32,6 +70,262 @@ fn profile_picture(profile_picture_properties: &ProfilePictureProperties) -> Htm
     }
 }
 
+#[derive(Properties, PartialEq)]
+struct QuotedMessageProperties {
+    pub preview: MessagePreview,
+}
+
+/// The message replied to, displayed above the reply.
+///
+/// Clicking on it scrolls to the original message if it is loaded.
+#[function_component(QuotedMessage)]
+fn quoted_message(quoted_message_properties: &QuotedMessageProperties) -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+    let translation = client_context
+        .user_context
+        .as_ref()
+        .map(|user_context| user_context.translation.clone())
+        .unwrap_or_default();
+    let preview = &quoted_message_properties.preview;
+    let uuid = preview.uuid;
+    let onclick = move |_| {
+        if let Some(element) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(&uuid.to_string()))
+        {
+            element.scroll_into_view();
+        }
+    };
+    let content: Html = match preview.is_deleted {
+        true => {
+            html! { <span class="italic">{translation.get_or_default("message_removed", "This message has been removed")}</span> }
+        }
+        false => html! { {&preview.content} },
+    };
+    html! {
+        <div class="text-xs border-l-2 border-gray-400 pl-2 mb-1 max-w-xs truncate cursor-pointer opacity-75 dark:text-gray-200" {onclick}>
+            <span class="font-semibold">{&preview.author_name}</span>{" : "}{content}
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct ReactionsProperties {
+    pub reactions: Vec<MessageReaction>,
+    pub on_toggle_reaction: Callback<String>,
+    pub is_user: bool,
+}
+
+/// The reactions made on a message, displayed as chips.
+///
+/// Clicking on a chip adds or removes the user's reaction.
+#[function_component(Reactions)]
+fn reactions(reactions_properties: &ReactionsProperties) -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+    let user_context = client_context.user_context.as_ref().cloned();
+    let current_user_id = user_context
+        .as_ref()
+        .map(|user_context| user_context.user.id);
+    let translation = user_context
+        .map(|user_context| user_context.translation)
+        .unwrap_or_default();
+    let is_picker_open = use_state(|| false);
+
+    let chips: Html = reactions_properties
+        .reactions
+        .iter()
+        .map(|reaction| {
+            let has_reacted = matches!(current_user_id, Some(id) if reaction.user_ids.contains(&id));
+            let class = match has_reacted {
+                true => "rounded-full px-2 text-xs border border-blue-600 bg-blue-100 dark:bg-blue-900 dark:text-gray-200",
+                false => "rounded-full px-2 text-xs border border-gray-300 bg-gray-100 dark:bg-zinc-700 dark:border-zinc-600 dark:text-gray-200",
+            };
+            let on_toggle_reaction = reactions_properties.on_toggle_reaction.clone();
+            let emoji = reaction.emoji.clone();
+            html! {
+                <button {class} onclick={move |_| on_toggle_reaction.emit(emoji.clone())}>
+                    {&reaction.emoji}{" "}{reaction.count}
+                </button>
+            }
+        })
+        .collect();
+
+    let picker: Option<Html> = match *is_picker_open {
+        true => Some(
+            DEFAULT_REACTIONS
+                .iter()
+                .map(|emoji| {
+                    let on_toggle_reaction = reactions_properties.on_toggle_reaction.clone();
+                    let is_picker_open = is_picker_open.clone();
+                    let onclick = move |_| {
+                        is_picker_open.set(false);
+                        on_toggle_reaction.emit(emoji.to_string());
+                    };
+                    html! {
+                        <button class="text-sm hover:scale-125" {onclick}>{emoji}</button>
+                    }
+                })
+                .collect(),
+        ),
+        false => None,
+    };
+
+    let class = match reactions_properties.is_user {
+        true => "flex flex-wrap gap-1 mb-2 mx-2",
+        false => "flex flex-wrap flex-row-reverse gap-1 mb-2 mx-2",
+    };
+    let toggle_picker = {
+        let is_picker_open = is_picker_open.clone();
+        move |_| is_picker_open.set(!*is_picker_open)
+    };
+    html! {
+        <div {class}>
+            {chips}
+            {picker}
+            <button class="rounded-full px-2 text-xs opacity-50 hover:opacity-100 dark:text-gray-200" title={translation.get_or_default("add_reaction", "React")} onclick={toggle_picker}>{"+"}</button>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct SeenByProperties {
+    pub receipts: Vec<MessageReceipt>,
+}
+
+#[function_component(SeenBy)]
+fn seen_by(seen_by_properties: &SeenByProperties) -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+    let translation = client_context
+        .user_context
+        .as_ref()
+        .map(|user_context| user_context.translation.clone())
+        .unwrap_or_default();
+    let is_expanded = use_state(|| false);
+    let readers: Vec<&MessageReceipt> = seen_by_properties
+        .receipts
+        .iter()
+        .filter(|receipt| receipt.seen_at.is_some())
+        .collect();
+    if readers.is_empty() {
+        return html! {};
+    }
+    let onclick = {
+        let is_expanded = is_expanded.clone();
+        move |_| is_expanded.set(!*is_expanded)
+    };
+    let reader_list = is_expanded.then(|| {
+        html! {
+            <ul class="text-xs dark:text-gray-200">
+            {readers.iter().map(|reader| html! { <li>{&reader.name}</li> }).collect::<Html>()}
+            </ul>
+        }
+    });
+    html! {
+        <div class="flex flex-col items-end mx-2">
+            <button class="italic text-xs opacity-75 dark:text-gray-200" {onclick}>
+                {format!("{} {}", translation.get_or_default("seen_by", "Seen by"), readers.len())}
+            </button>
+            {reader_list}
+        </div>
+    }
+}
+
+/// Creates a URL from which the browser can read a downloaded file.
+fn object_url(content: &[u8]) -> Option<String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(content));
+    let blob = Blob::new_with_u8_array_sequence(&parts).ok()?;
+    Url::create_object_url_with_blob(&blob).ok()
+}
+
+#[derive(Properties, PartialEq)]
+struct AttachmentItemProperties {
+    pub attachment: Attachment,
+}
+
+/// A file joined to a message, the images being previewed.
+///
+/// The attachments can only be downloaded with the user's credentials, so
+/// they are retrieved before being displayed.
+#[function_component(AttachmentItem)]
+fn attachment_item(attachment_item_properties: &AttachmentItemProperties) -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+    let url = use_state(|| None::<String>);
+    // Kept to release the downloaded file once the attachment is no longer
+    // displayed.
+    let created_url = use_mut_ref(|| None::<String>);
+    {
+        let url = url.clone();
+        let bearer = client_context.bearer.clone();
+        use_effect_with_deps(
+            move |attachment: &Attachment| {
+                let endpoint = attachment.url();
+                let created = created_url.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let mut req = Requester::get(&endpoint);
+                    let resp = req.bearer(bearer).send().await;
+                    if !resp.ok() {
+                        return;
+                    }
+                    if let Ok(content) = resp.binary().await {
+                        let object_url = object_url(&content);
+                        *created.borrow_mut() = object_url.clone();
+                        url.set(object_url);
+                    }
+                });
+                move || {
+                    if let Some(object_url) = created_url.borrow_mut().take() {
+                        let _ = Url::revoke_object_url(&object_url);
+                    }
+                }
+            },
+            attachment_item_properties.attachment.clone(),
+        );
+    }
+    let attachment = &attachment_item_properties.attachment;
+    match (attachment.is_image(), (*url).clone()) {
+        (true, Some(url)) => html! {
+            <a href={url.clone()} target="_blank" rel="noopener noreferrer">
+                <img class="max-h-48 max-w-xs rounded-lg" src={url} alt={attachment.file_name.clone()} loading="lazy" />
+            </a>
+        },
+        (_, url) => html! {
+            <a class="flex items-center gap-2 rounded-lg bg-gray-200 dark:bg-zinc-700 dark:text-gray-200 text-xs px-3 py-2 max-w-xs" href={url} download={attachment.file_name.clone()}>
+                <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-4 h-4 shrink-0">
+                    <path stroke-linecap="round" stroke-linejoin="round" d="M3 16.5v2.25A2.25 2.25 0 005.25 21h13.5A2.25 2.25 0 0021 18.75V16.5M16.5 12L12 16.5m0 0L7.5 12m4.5 4.5V3" />
+                </svg>
+                <span class="truncate">{&attachment.file_name}</span>
+                <span class="opacity-75 shrink-0">{format_file_size(attachment.size)}</span>
+            </a>
+        },
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct AttachmentsProperties {
+    pub attachments: Vec<Attachment>,
+    #[prop_or_default]
+    pub is_user: bool,
+}
+
+/// The files joined to a message.
+#[function_component(Attachments)]
+fn attachments(attachments_properties: &AttachmentsProperties) -> Html {
+    if attachments_properties.attachments.is_empty() {
+        return html! {};
+    }
+    let class = match attachments_properties.is_user {
+        true => "flex flex-col items-end gap-1 mb-2 mx-2",
+        false => "flex flex-col items-start gap-1 mb-2 mx-2",
+    };
+    html! {
+        <div {class}>
+            { attachments_properties.attachments.iter().map(|attachment| html! {
+                <AttachmentItem key={attachment.id.to_string()} attachment={attachment.clone()} />
+            }).collect::<Html>() }
+        </div>
+    }
+}
+
 #[derive(Properties, PartialEq)]
 struct MessageProperties {
     pub content: AttrValue,
@@ -40,32 +334,85 @@ struct MessageProperties {
     #[prop_or_default]
     pub is_user: bool,
     pub reception_status: WsReceptionStatus,
+    #[prop_or_default]
+    pub edited_at: Option<DateTime<Utc>>,
+    #[prop_or_default]
+    pub on_edit: Option<Callback<String>>,
+    #[prop_or_default]
+    pub is_deleted: bool,
+    #[prop_or_default]
+    pub on_delete: Option<Callback<()>>,
+    #[prop_or_default]
+    pub reply_preview: Option<MessagePreview>,
+    #[prop_or_default]
+    pub on_reply: Option<Callback<()>>,
+    #[prop_or_default]
+    pub reactions: Vec<MessageReaction>,
+    #[prop_or_default]
+    pub on_toggle_reaction: Option<Callback<String>>,
+    #[prop_or_default]
+    pub receipts: Vec<MessageReceipt>,
+    #[prop_or_default]
+    pub attachments: Vec<Attachment>,
+    #[prop_or_default]
+    pub is_pinned: bool,
+    #[prop_or_default]
+    pub on_toggle_pin: Option<Callback<()>>,
 }
 
 #[function_component(Message)]
 fn message(message_properties: &MessageProperties) -> Html {
-    let timestamp = &message_properties.timestamp;
-    let title: AttrValue = format!(
-        "on {:02}/{:02}/{} at {}:{:02}",
-        timestamp.day(),
-        timestamp.month(),
-        timestamp.year(),
-        timestamp.hour(),
-        timestamp.minute()
-    )
-    .into();
-    let class: &str = match message_properties.is_user {
-        true => {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+    let user_context = client_context.user_context.as_ref().cloned();
+    let translation = user_context
+        .as_ref()
+        .map(|user_context| user_context.translation.clone())
+        .unwrap_or_default();
+    let user_offset = Duration::seconds(
+        user_context
+            .as_ref()
+            .map(|user_context| user_context.user.timezone.tz_offset)
+            .unwrap_or_default(),
+    );
+    let is_editing = use_state(|| false);
+    let revisions = use_state(|| None::<Vec<MessageRevision>>);
+    let edit_input = use_node_ref();
+
+    let login = user_context
+        .as_ref()
+        .map(|user_context| user_context.user.login.clone())
+        .unwrap_or_default();
+    let is_mentioned = !message_properties.is_user
+        && !message_properties.is_deleted
+        && mentioned_logins(&message_properties.content).contains(&login);
+
+    let title: AttrValue = format_timestamp(&message_properties.timestamp).into();
+    let class: &str = match (message_properties.is_user, is_mentioned) {
+        (true, _) => {
             "relative bg-blue-600 text-white p-3 rounded-l-lg rounded-br-lg mb-2 text-sm break-when-needed max-w-xs"
         }
-        false => "relative bg-gray-300 mb-2 p-3 rounded-r-lg rounded-bl-lg text-sm break-when-needed max-w-xs",
+        (false, true) => "relative bg-gray-300 mb-2 p-3 rounded-r-lg rounded-bl-lg text-sm break-when-needed max-w-xs ring-2 ring-yellow-400",
+        (false, false) => "relative bg-gray-300 mb-2 p-3 rounded-r-lg rounded-bl-lg text-sm break-when-needed max-w-xs",
     };
     let reception_checkmark = match message_properties.reception_status {
+        // The message hasn't been acknowledged by the server yet.
+        WsReceptionStatus::NotSent if message_properties.is_user => Some(html! {
+            <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-2 h-2">
+                <path stroke-linecap="round" stroke-linejoin="round" d="M12 6v6h4.5m4.5 0a9 9 0 11-18 0 9 9 0 0118 0z" />
+            </svg>
+
+        }),
         WsReceptionStatus::Sent if message_properties.is_user => Some(html! {
             <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-2 h-2">
              <path stroke-linecap="round" stroke-linejoin="round" d="M4.5 12.75l6 6 9-13.5" />
             </svg>
 
+        }),
+        WsReceptionStatus::Delivered if message_properties.is_user => Some(html! {
+            <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-2 h-2">
+             <path stroke-linecap="round" stroke-linejoin="round" d="M1.5 12.75l6 6 9-13.5M9.75 16.5l2.25 2.25 9-13.5" />
+            </svg>
+
         }),
         WsReceptionStatus::Seen if message_properties.is_user => Some(html! {
             <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-2 h-2">
@@ -80,16 +427,194 @@ fn message(message_properties: &MessageProperties) -> Html {
         false => "flex flex-row-reverse",
     };
 
+    let edited_marker = message_properties.edited_at.map(|_| {
+        let revisions = revisions.clone();
+        let bearer = client_context.bearer.clone();
+        let uuid = message_properties.uuid;
+        let onclick = move |me: MouseEvent| {
+            me.stop_propagation();
+            if revisions.is_some() {
+                revisions.set(None);
+                return;
+            }
+            let revisions = revisions.clone();
+            let bearer = bearer.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let mut req = Requester::get(&format!("/api/message/{uuid}/revisions"));
+                let resp = req.bearer(bearer).send().await;
+                if resp.ok() {
+                    let retrieved: Vec<MessageRevision> =
+                        serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                    revisions.set(Some(retrieved));
+                }
+            });
+        };
+        html! {
+            <button class="italic text-xs opacity-75 ml-1" {onclick}>
+                {translation.get_or_default("message_edited", "(edited)")}
+            </button>
+        }
+    });
+
+    let revision_history = revisions.as_ref().map(|revisions| {
+        html! {
+            <div class="text-xs dark:text-gray-200 mx-2 mb-2 max-w-xs">
+                <p class="font-semibold">{translation.get_or_default("message_previous_versions", "Previous versions")}</p>
+                <ul>
+                {revisions.iter().map(|revision| html! {
+                    <li title={format_timestamp(&(revision.revised_at + user_offset))} class="break-when-needed line-through">{&revision.content}</li>
+                }).collect::<Html>()}
+                </ul>
+            </div>
+        }
+    });
+
+    let content: Html = match (*is_editing, &message_properties.on_edit) {
+        _ if message_properties.is_deleted => html! {
+            <span class="italic opacity-75">{translation.get_or_default("message_removed", "This message has been removed")}</span>
+        },
+        (true, Some(on_edit)) => {
+            let on_edit = on_edit.clone();
+            let is_editing = is_editing.clone();
+            let input_ref = edit_input.clone();
+            let onsubmit = move |se: SubmitEvent| {
+                se.prevent_default();
+                if let Some(input) = input_ref.cast::<HtmlInputElement>() {
+                    if input.check_validity() && !input.value().is_empty() {
+                        on_edit.emit(input.value());
+                        is_editing.set(false);
+                    }
+                }
+            };
+            html! {
+                <form {onsubmit} action="javascript:void(0);">
+                    <input class="rounded px-1 text-gray-700 invalid:border-red-500" type="text" minlength="2" maxlength="127" value={message_properties.content.clone()} ref={edit_input} onclick={|me: MouseEvent| me.stop_propagation()} />
+                </form>
+            }
+        }
+        _ => html! { <>{render_markdown(&message_properties.content, &login)}{edited_marker}</> },
+    };
+
+    let edit_button = match (*is_editing, &message_properties.on_edit) {
+        _ if message_properties.is_deleted => None,
+        (false, Some(_)) => {
+            let is_editing = is_editing.clone();
+            Some(html! {
+                <button class="self-center mx-1 mb-2 opacity-50 hover:opacity-100 dark:text-gray-200" title={translation.get_or_default("edit_message", "Edit")} onclick={move |_| is_editing.set(true)}>
+                    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-3 h-3">
+                        <path stroke-linecap="round" stroke-linejoin="round" d="M16.862 4.487l1.687-1.688a1.875 1.875 0 112.652 2.652L6.832 19.82a4.5 4.5 0 01-1.897 1.13l-2.685.8.8-2.685a4.5 4.5 0 011.13-1.897L16.863 4.487z" />
+                    </svg>
+                </button>
+            })
+        }
+        _ => None,
+    };
+
+    let delete_button = match &message_properties.on_delete {
+        Some(on_delete) if !message_properties.is_deleted => {
+            let on_delete = on_delete.clone();
+            Some(html! {
+                <button class="self-center mx-1 mb-2 opacity-50 hover:opacity-100 dark:text-gray-200" title={translation.get_or_default("delete_message", "Delete")} onclick={move |_| on_delete.emit(())}>
+                    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-3 h-3">
+                        <path stroke-linecap="round" stroke-linejoin="round" d="M14.74 9l-.346 9m-4.788 0L9.26 9m9.968-3.21c.342.052.682.107 1.022.166m-1.022-.165L18.16 19.673a2.25 2.25 0 01-2.244 2.077H8.084a2.25 2.25 0 01-2.244-2.077L4.772 5.79m14.456 0a48.108 48.108 0 00-3.478-.397m-12 .562c.34-.059.68-.114 1.022-.165m0 0a48.11 48.11 0 013.478-.397m7.5 0v-.916c0-1.18-.91-2.164-2.09-2.201a51.964 51.964 0 00-3.32 0c-1.18.037-2.09 1.022-2.09 2.201v.916m7.5 0a48.667 48.667 0 00-7.5 0" />
+                    </svg>
+                </button>
+            })
+        }
+        _ => None,
+    };
+
+    let pin_button = match &message_properties.on_toggle_pin {
+        Some(on_toggle_pin) if !message_properties.is_deleted => {
+            let on_toggle_pin = on_toggle_pin.clone();
+            let (title, class) = match message_properties.is_pinned {
+                true => (
+                    translation.get_or_default("unpin_message", "Unpin"),
+                    "self-center mx-1 mb-2 opacity-100 dark:text-gray-200",
+                ),
+                false => (
+                    translation.get_or_default("pin_message", "Pin"),
+                    "self-center mx-1 mb-2 opacity-50 hover:opacity-100 dark:text-gray-200",
+                ),
+            };
+            Some(html! {
+                <button {class} {title} onclick={move |_| on_toggle_pin.emit(())}>
+                    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-3 h-3">
+                        <path stroke-linecap="round" stroke-linejoin="round" d="M17.593 3.322c1.1.128 1.907 1.077 1.907 2.185V21L12 17.25 4.5 21V5.507c0-1.108.806-2.057 1.907-2.185a48.507 48.507 0 0111.186 0z" />
+                    </svg>
+                </button>
+            })
+        }
+        _ => None,
+    };
+
+    let reply_button = match &message_properties.on_reply {
+        Some(on_reply) if !message_properties.is_deleted => {
+            let on_reply = on_reply.clone();
+            Some(html! {
+                <button class="self-center mx-1 mb-2 opacity-50 hover:opacity-100 dark:text-gray-200" title={translation.get_or_default("reply_message", "Reply")} onclick={move |_| on_reply.emit(())}>
+                    <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-3 h-3">
+                        <path stroke-linecap="round" stroke-linejoin="round" d="M9 15L3 9m0 0l6-6M3 9h12a6 6 0 010 12h-3" />
+                    </svg>
+                </button>
+            })
+        }
+        _ => None,
+    };
+
+    let quoted_message = message_properties
+        .reply_preview
+        .clone()
+        .map(|preview| html! { <QuotedMessage {preview} /> });
+    let quote_class = match message_properties.is_user {
+        true => "flex",
+        false => "flex flex-row-reverse",
+    };
+
+    let reactions = match &message_properties.on_toggle_reaction {
+        Some(on_toggle_reaction) if !message_properties.is_deleted => Some(html! {
+            <Reactions reactions={message_properties.reactions.clone()} on_toggle_reaction={on_toggle_reaction.clone()} is_user={message_properties.is_user} />
+        }),
+        _ => None,
+    };
+
+    // The messages written while disconnected wait in the outbox until the
+    // server acknowledges them.
+    let queued = (message_properties.is_user
+        && message_properties.reception_status == WsReceptionStatus::NotSent)
+        .then(|| {
+            html! {
+                <small class="italic opacity-75 dark:text-gray-200 mx-2">{translation.get_or_default("message_queued", "Queued")}</small>
+            }
+        });
+
+    let seen_by = match message_properties.is_user && !message_properties.is_deleted {
+        true => Some(html! { <SeenBy receipts={message_properties.receipts.clone()} /> }),
+        false => None,
+    };
+
     let hide_timestamp = use_state(|| true);
     html! {
-        <div id={message_properties.uuid.to_string()} class={div_class}>
-            <small hidden={*hide_timestamp} class="dark:text-white mx-2">{&title}</small>
-            <p {title} class={class} onclick={move |_me| hide_timestamp.set(!*hide_timestamp)} >
-                {message_properties.content.as_str()}
-                    <span class="absolute right-0 bottom-0 pb-1 pr-1">
-                    {reception_checkmark}
-                    </span>
-            </p>
+        <div id={message_properties.uuid.to_string()} class="flex flex-col">
+            <div class={quote_class}>{quoted_message}</div>
+            <div class={div_class}>
+                <small hidden={*hide_timestamp} class="dark:text-white mx-2">{&title}</small>
+                {reply_button}
+                {pin_button}
+                {delete_button}
+                {edit_button}
+                <p {title} class={class} onclick={move |_me| hide_timestamp.set(!*hide_timestamp)} >
+                    {content}
+                        <span class="absolute right-0 bottom-0 pb-1 pr-1">
+                        {reception_checkmark}
+                        </span>
+                </p>
+            </div>
+            <Attachments attachments={message_properties.attachments.clone()} is_user={message_properties.is_user} />
+            {queued}
+            {reactions}
+            {seen_by}
+            {revision_history}
         </div>
     }
 }
@@ -107,6 +632,30 @@ struct UserChatProperties {
     #[prop_or(true)]
     pub display_pfp: bool,
     pub reception_status: WsReceptionStatus,
+    #[prop_or_default]
+    pub edited_at: Option<DateTime<Utc>>,
+    #[prop_or_default]
+    pub on_edit: Option<Callback<String>>,
+    #[prop_or_default]
+    pub is_deleted: bool,
+    #[prop_or_default]
+    pub on_delete: Option<Callback<()>>,
+    #[prop_or_default]
+    pub reply_preview: Option<MessagePreview>,
+    #[prop_or_default]
+    pub on_reply: Option<Callback<()>>,
+    #[prop_or_default]
+    pub reactions: Vec<MessageReaction>,
+    #[prop_or_default]
+    pub on_toggle_reaction: Option<Callback<String>>,
+    #[prop_or_default]
+    pub receipts: Vec<MessageReceipt>,
+    #[prop_or_default]
+    pub attachments: Vec<Attachment>,
+    #[prop_or_default]
+    pub is_pinned: bool,
+    #[prop_or_default]
+    pub on_toggle_pin: Option<Callback<()>>,
 }
 
 #[function_component(UserChat)]
@@ -121,16 +670,42 @@ fn user_chat(user_chat_properties: &UserChatProperties) -> Html {
     html! {
         <div {class}>
             <ProfilePicture pfp={user_chat_properties.pfp.clone()} author={user_chat_properties.author.clone()} display_pfp={user_chat_properties.display_pfp} />
-            <Message uuid={user_chat_properties.uuid} reception_status={user_chat_properties.reception_status} content={user_chat_properties.content.clone()} is_user={user_chat_properties.is_user} timestamp={user_chat_properties.timestamp} />
+            <Message uuid={user_chat_properties.uuid} reception_status={user_chat_properties.reception_status} content={user_chat_properties.content.clone()} is_user={user_chat_properties.is_user} timestamp={user_chat_properties.timestamp} edited_at={user_chat_properties.edited_at} on_edit={user_chat_properties.on_edit.clone()} is_deleted={user_chat_properties.is_deleted} on_delete={user_chat_properties.on_delete.clone()} reply_preview={user_chat_properties.reply_preview.clone()} on_reply={user_chat_properties.on_reply.clone()} reactions={user_chat_properties.reactions.clone()} on_toggle_reaction={user_chat_properties.on_toggle_reaction.clone()} receipts={user_chat_properties.receipts.clone()} attachments={user_chat_properties.attachments.clone()} is_pinned={user_chat_properties.is_pinned} on_toggle_pin={user_chat_properties.on_toggle_pin.clone()} />
         </div>
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Properties)]
+/// Separates the messages already read from the new ones.
+#[function_component(NewMessagesDivider)]
+fn new_messages_divider() -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+    let translation = client_context
+        .user_context
+        .as_ref()
+        .map(|user_context| user_context.translation.clone())
+        .unwrap_or_default();
+    html! {
+        <div class="flex items-center gap-2 px-3 my-2 text-xs text-red-600 dark:text-red-400">
+            <hr class="flex-grow border-red-600 dark:border-red-400" />
+            <span>{translation.get_or_default("new_messages", "New messages")}</span>
+            <hr class="flex-grow border-red-600 dark:border-red-400" />
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
 pub struct Props {
     pub messages: Vec<WsMessageContent>,
     pub room: AttrValue,
     pub user: PartialUser,
+    pub pass_message_to_ws: Callback<String>,
+    pub on_reply: Callback<MessagePreview>,
+    /// The messages pinned in the room.
+    #[prop_or_default]
+    pub pinned: Vec<Uuid>,
+    /// The last message the user read before opening the room.
+    #[prop_or_default]
+    pub last_read: Option<Uuid>,
 }
 
 pub struct Chat;
@@ -149,6 +724,11 @@ impl Component for Chat {
         let mut html_content: Vec<Html> = Vec::with_capacity(ctx.props().messages.len());
         let user_offset = Duration::seconds(ctx.props().user.timezone.tz_offset);
         let current_user_id = ctx.props().user.id;
+        let is_moderator = ctx.props().user.profile >= Profile::Moderator;
+        let can_pin = can_pin(&ctx.props().room, &ctx.props().user);
+        // Whether a message written by another user follows the one being
+        // built.
+        let mut has_newer_messages = false;
         while let Some(current_element) = std::mem::replace(&mut next_element_opt, iterator.next())
         {
             let display_pfp = match next_element_opt {
@@ -157,7 +737,81 @@ impl Component for Chat {
                 // so we display the pfp for the first message
                 _ => true,
             };
-            html_content.push(html! { <UserChat uuid={current_element.uuid} pfp={current_element.author.pfp.clone().unwrap_or_else(|| DEFAULT_PFP.into())} reception_status={current_element.reception_status} content={current_element.content.clone()} author={current_element.author.name.clone()} is_user={current_element.author.id == current_user_id} timestamp={current_element.timestamp + user_offset} {display_pfp}/> });
+            let is_user = current_element.author.id == current_user_id;
+            let on_edit: Option<Callback<String>> = match is_user {
+                true => {
+                    let pass_message_to_ws = ctx.props().pass_message_to_ws.clone();
+                    let uuid = current_element.uuid;
+                    Some(Callback::from(move |content: String| {
+                        pass_message_to_ws.emit(
+                            serde_json::to_string(&WsMessage::Edit { uuid, content }).unwrap(),
+                        )
+                    }))
+                }
+                false => None,
+            };
+            let on_delete: Option<Callback<()>> = match is_user || is_moderator {
+                true => {
+                    let pass_message_to_ws = ctx.props().pass_message_to_ws.clone();
+                    let uuid = current_element.uuid;
+                    Some(Callback::from(move |_| {
+                        pass_message_to_ws
+                            .emit(serde_json::to_string(&WsMessage::Delete(vec![uuid])).unwrap())
+                    }))
+                }
+                false => None,
+            };
+            let on_reply: Callback<()> = {
+                let on_reply = ctx.props().on_reply.clone();
+                let preview = MessagePreview {
+                    uuid: current_element.uuid,
+                    author_name: current_element.author.name.clone(),
+                    content: current_element
+                        .content
+                        .chars()
+                        .take(PREVIEW_LENGTH as usize)
+                        .collect(),
+                    is_deleted: false,
+                };
+                Callback::from(move |_| on_reply.emit(preview.clone()))
+            };
+            let is_pinned = ctx.props().pinned.contains(&current_element.uuid);
+            let on_toggle_pin: Option<Callback<()>> = match can_pin {
+                true => {
+                    let pass_message_to_ws = ctx.props().pass_message_to_ws.clone();
+                    let uuid = current_element.uuid;
+                    Some(Callback::from(move |_| {
+                        let msg = match is_pinned {
+                            true => WsMessage::Unpin(uuid),
+                            false => WsMessage::Pin(uuid),
+                        };
+                        pass_message_to_ws.emit(serde_json::to_string(&msg).unwrap())
+                    }))
+                }
+                false => None,
+            };
+            let on_toggle_reaction: Callback<String> = {
+                let pass_message_to_ws = ctx.props().pass_message_to_ws.clone();
+                let uuid = current_element.uuid;
+                let reactions = current_element.reactions.clone();
+                Callback::from(move |emoji: String| {
+                    let has_reacted = reactions.iter().any(|reaction| {
+                        reaction.emoji == emoji && reaction.user_ids.contains(&current_user_id)
+                    });
+                    let msg = match has_reacted {
+                        true => WsMessage::Unreact { uuid, emoji },
+                        false => WsMessage::React { uuid, emoji },
+                    };
+                    pass_message_to_ws.emit(serde_json::to_string(&msg).unwrap())
+                })
+            };
+            // The chat is built in reverse order, so the divider is pushed
+            // before the last message read to be displayed below it.
+            if has_newer_messages && ctx.props().last_read == Some(current_element.uuid) {
+                html_content.push(html! { <NewMessagesDivider /> });
+            }
+            has_newer_messages |= !is_user;
+            html_content.push(html! { <UserChat uuid={current_element.uuid} pfp={current_element.author.pfp.clone().unwrap_or_else(|| DEFAULT_PFP.into())} reception_status={current_element.reception_status} content={current_element.content.clone()} author={current_element.author.name.clone()} {is_user} timestamp={current_element.timestamp + user_offset} edited_at={current_element.edited_at} {on_edit} is_deleted={current_element.deleted_at.is_some()} {on_delete} reply_preview={current_element.reply_preview.clone()} {on_reply} reactions={current_element.reactions.clone()} {on_toggle_reaction} receipts={current_element.receipts.clone()} attachments={current_element.attachments.clone()} {is_pinned} {on_toggle_pin} {display_pfp}/> });
         }
         html_content.into_iter().collect::<Html>()
     }
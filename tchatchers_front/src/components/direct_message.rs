@@ -0,0 +1,213 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use std::rc::Rc;
+
+use super::chat::DEFAULT_PFP;
+use super::feed::Feed;
+use super::toast::Alert;
+use crate::router::Route;
+use crate::services::toast_bus::ToastBus;
+use crate::utils::client_context::ClientContext;
+use crate::utils::requester::Requester;
+use tchatchers_core::direct_message::DirectConversation;
+use yew::{function_component, html, use_context, AttrValue, Component, Context, Html, Properties};
+use yew_agent::Dispatched;
+use yew_router::prelude::Link;
+use yew_router::scope_ext::RouterScopeExt;
+
+/// The number of characters of the last message displayed in the list of
+/// conversations.
+const LAST_MESSAGE_PREVIEW_LENGTH: usize = 60;
+
+#[derive(Properties, PartialEq, Clone)]
+pub struct DirectMessageHOCProps {
+    pub login: AttrValue,
+}
+
+#[function_component(DirectMessageHOC)]
+pub fn direct_message_hoc(props: &DirectMessageHOCProps) -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+
+    html! { <DirectMessage login={props.login.clone()} client_context={client_context} /> }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub login: AttrValue,
+    pub client_context: Rc<ClientContext>,
+}
+
+pub enum Msg {
+    ConversationRetrieved(Box<DirectConversation>),
+    ConversationNotFound(String),
+}
+
+/// The private conversation between the user and the one whose login is
+/// given.
+#[derive(Default)]
+pub struct DirectMessage {
+    conversation: Option<DirectConversation>,
+}
+
+impl DirectMessage {
+    /// Retrieves the conversation with the user bound to the login.
+    fn request_conversation(ctx: &Context<Self>) {
+        let mut req = Requester::get(&format!(
+            "/api/dm/{}",
+            js_sys::encode_uri_component(&ctx.props().login)
+        ));
+        req.bearer(ctx.props().client_context.bearer.clone());
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let conversation: DirectConversation =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(Msg::ConversationRetrieved(Box::new(conversation)));
+            } else {
+                link.send_message(Msg::ConversationNotFound(resp.text().await.unwrap()));
+            }
+        });
+    }
+}
+
+impl Component for DirectMessage {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self::request_conversation(ctx);
+        Self::default()
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ConversationRetrieved(conversation) => {
+                self.conversation = Some(*conversation);
+                true
+            }
+            Msg::ConversationNotFound(reason) => {
+                ToastBus::dispatcher().send(Alert {
+                    is_success: false,
+                    content: reason,
+                });
+                ctx.link().navigator().unwrap().replace(&Route::JoinRoom);
+                false
+            }
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        self.conversation = None;
+        Self::request_conversation(ctx);
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        match &self.conversation {
+            Some(conversation) => {
+                let room: AttrValue = conversation.room.clone().into();
+                html! { <Feed key={conversation.room.clone()} {room} client_context={ctx.props().client_context.clone()} /> }
+            }
+            None => html! { <div class="h-full dark:bg-zinc-800"></div> },
+        }
+    }
+}
+
+#[function_component(DirectConversationsHOC)]
+pub fn direct_conversations_hoc() -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+
+    html! { <DirectConversations client_context={client_context} /> }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct DirectConversationsProps {
+    pub client_context: Rc<ClientContext>,
+}
+
+pub enum DirectConversationsMsg {
+    ConversationsRetrieved(Vec<DirectConversation>),
+}
+
+/// Lists the direct conversations of the user, the most recently active
+/// first.
+#[derive(Default)]
+pub struct DirectConversations {
+    conversations: Vec<DirectConversation>,
+}
+
+impl Component for DirectConversations {
+    type Message = DirectConversationsMsg;
+    type Properties = DirectConversationsProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut req = Requester::get("/api/dm");
+        req.bearer(ctx.props().client_context.bearer.clone());
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let conversations: Vec<DirectConversation> =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(DirectConversationsMsg::ConversationsRetrieved(
+                    conversations,
+                ));
+            }
+        });
+        Self::default()
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            DirectConversationsMsg::ConversationsRetrieved(conversations) => {
+                self.conversations = conversations;
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.conversations.is_empty() {
+            return html! {};
+        }
+        let translation = ctx
+            .props()
+            .client_context
+            .user_context
+            .as_ref()
+            .map(|user_context| user_context.translation.clone())
+            .unwrap_or_default();
+        html! {
+            <div class="w-full max-w-sm border-2 dark:border-zinc-700 px-6 py-4 mt-4 dark:text-gray-200">
+                <h2 class="text-lg mb-2 text-gray-500 dark:text-gray-200 font-bold">
+                    {translation.get_or_default("direct_messages", "Direct messages")}
+                </h2>
+                <ul>
+                    { self.conversations.iter().map(|conversation| {
+                        let interlocutor = &conversation.interlocutor;
+                        let last_message = conversation.last_message.as_ref().map(|message| {
+                            if message.deleted_at.is_some() {
+                                translation.get_or_default("message_removed", "This message has been removed")
+                            } else {
+                                message.content.chars().take(LAST_MESSAGE_PREVIEW_LENGTH).collect()
+                            }
+                        });
+                        html! {
+                            <li class="py-1">
+                                <Link<Route> to={Route::DirectMessage { login: interlocutor.login.clone() }} classes="flex items-center gap-2">
+                                    <img class="h-8 w-8 rounded-full bg-gray-300" src={interlocutor.pfp.clone().unwrap_or_else(|| DEFAULT_PFP.into())} alt="No img"/>
+                                    <div class="min-w-0">
+                                        <div class="font-semibold truncate">{&interlocutor.name}</div>
+                                        <div class="text-sm text-gray-500 dark:text-gray-400 truncate">{last_message}</div>
+                                    </div>
+                                </Link<Route>>
+                            </li>
+                        }
+                    }).collect::<Html>() }
+                </ul>
+            </div>
+        }
+    }
+}
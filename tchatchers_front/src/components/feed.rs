@@ -3,23 +3,36 @@ use std::rc::Rc;
 // Copyright ⓒ 2022 LABEYE Loïc
 // This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
 use super::chat::Chat;
+use super::common::WaitingForResponse;
 use super::disconnected_bar::DisconnectedBar;
+use super::member_list::MemberList;
+use super::pinned_banner::PinnedBanner;
+use super::search_panel::SearchPanel;
 use super::type_bar::TypeBar;
+use super::unread_banner::UnreadBanner;
 use crate::components::toast::Alert;
 use crate::router::Route;
 use crate::services::chat_bus::ChatBus;
 use crate::services::chat_service::WebsocketService;
 use crate::services::toast_bus::ToastBus;
 use crate::utils::client_context::ClientContext;
+use crate::utils::outbox;
 use crate::utils::requester::Requester;
 use gloo_timers::callback::{Interval, Timeout};
 use tchatchers_core::app_context::UserContext;
-use tchatchers_core::room::RoomNameValidator;
-use tchatchers_core::ws_message::{WsMessage, WsMessageContent, WsReceptionStatus};
+use tchatchers_core::pin::{can_pin, PinnedMessage};
+use tchatchers_core::read_marker::UnreadCount;
+use tchatchers_core::receipt::MessageReceipt;
+use tchatchers_core::room::{RoomMember, RoomNameValidator};
+use tchatchers_core::ws_message::{
+    MessageCursor, MessagePreview, TypingUser, WsMessage, WsMessageContent, WsReceptionStatus,
+    DEFAULT_PAGE_SIZE, PREVIEW_LENGTH,
+};
 use uuid::Uuid;
 use validator::Validate;
+use web_sys::Element;
 use yew::{
-    function_component, html, use_context, AttrValue, Callback, Component, Context, Html,
+    function_component, html, use_context, AttrValue, Callback, Component, Context, Html, NodeRef,
     Properties, UseStateHandle,
 };
 use yew_agent::Dispatched;
@@ -38,12 +51,25 @@ pub fn feed_hoc(props: &FeedHOCProps) -> Html {
     html! { <Feed room={props.room.clone()} client_context={client_context} /> }
 }
 
+/// The distance in pixels from the top of the chat under which older messages
+/// are requested.
+const LOAD_OLDER_MESSAGES_THRESHOLD: i32 = 64;
+
 #[derive(Clone)]
 pub enum Msg {
     HandleWsInteraction(Box<WsMessage>),
     CheckWsState,
     TryReconnect,
     CutWs,
+    ChatScrolled,
+    ReplyTo(Option<MessagePreview>),
+    MembersRetrieved(Vec<RoomMember>),
+    ToggleMembers,
+    PinsRetrieved(Vec<PinnedMessage>),
+    JumpTo(Uuid),
+    UnreadCountsRetrieved(Vec<UnreadCount>),
+    Send(Box<WsMessageContent>),
+    FlushOutbox,
 }
 
 #[derive(Clone, PartialEq, Properties)]
@@ -65,6 +91,212 @@ pub struct Feed {
     room_name_checked: bool,
     user_context: UserContext,
     bearer: UseStateHandle<Option<String>>,
+    chat_ref: NodeRef,
+    has_more_messages: bool,
+    is_loading_messages: bool,
+    reply_to: Option<MessagePreview>,
+    typing_users: Vec<TypingUser>,
+    members: Vec<RoomMember>,
+    is_member_list_collapsed: bool,
+    /// The message to scroll to once it is loaded.
+    pending_jump: Option<Uuid>,
+    /// The message to scroll to once the feed is rendered.
+    scroll_to: Option<Uuid>,
+    /// The messages pinned in the room.
+    pins: Vec<PinnedMessage>,
+    /// The number of unread messages in the other rooms the user follows.
+    unread: Vec<UnreadCount>,
+    /// The last message the user read before opening the room.
+    last_read: Option<Uuid>,
+    /// Whether the unread counts have already been retrieved once.
+    unread_retrieved: bool,
+    /// The messages sent by the user that the server hasn't acknowledged yet,
+    /// from the oldest to the most recent.
+    outbox: Vec<WsMessageContent>,
+    /// Sends the outbox again once the server accepts new messages.
+    outbox_retry: Option<Timeout>,
+}
+
+impl Feed {
+    /// Asks the server for the page of messages preceding the given cursor.
+    ///
+    /// The most recent messages are requested when no cursor is given.
+    fn request_messages_page(&mut self, before: Option<MessageCursor>) {
+        self.is_loading_messages = true;
+        let msg = WsMessage::RetrieveMessagesPage {
+            session_id: self.session_id,
+            before,
+            page_size: DEFAULT_PAGE_SIZE,
+        };
+        self.ws
+            .tx
+            .clone()
+            .try_send(serde_json::to_string(&msg).unwrap())
+            .unwrap();
+    }
+
+    /// Sends the oldest message of the outbox to the server.
+    ///
+    /// The messages are sent one at a time, the next one being sent once the
+    /// previous one is acknowledged, so that they are received in order.
+    fn flush_outbox(&self) {
+        let Some(message) = self.outbox.first() else {
+            return;
+        };
+        if self.is_connected {
+            let _ = self
+                .ws
+                .tx
+                .clone()
+                .try_send(serde_json::to_string(&WsMessage::Send(message.clone())).unwrap());
+        }
+    }
+
+    /// Removes a message from the outbox, sending the next one if it was the
+    /// oldest.
+    ///
+    /// Returns the removed message, if it was in the outbox.
+    fn remove_from_outbox(&mut self, ctx: &Context<Self>, uuid: Uuid) -> Option<WsMessageContent> {
+        let index = self.outbox.iter().position(|m| m.uuid == uuid)?;
+        let message = self.outbox.remove(index);
+        outbox::store(&ctx.props().room, &self.outbox);
+        if index == 0 {
+            self.flush_outbox();
+        }
+        Some(message)
+    }
+
+    /// Whether the message has been written by another user and hasn't been
+    /// seen by the current one yet.
+    fn is_unseen(&self, message: &WsMessageContent) -> bool {
+        let user_id = self.user_context.user.id;
+        message.author.id != user_id
+            && !message
+                .receipts
+                .iter()
+                .any(|receipt| receipt.user_id == user_id && receipt.seen_at.is_some())
+    }
+
+    /// Appends the retrieved messages to the feed, and informs the server
+    /// the ones written by other users have been seen.
+    fn append_retrieved_messages(&mut self, mut messages: Vec<WsMessageContent>) {
+        let messages_seen: Vec<Uuid> = messages
+            .iter()
+            .filter(|message| self.is_unseen(message))
+            .map(|m| m.uuid)
+            .collect();
+        self.received_messages.append(&mut messages);
+
+        if !messages_seen.is_empty() {
+            self.ws
+                .tx
+                .clone()
+                .try_send(serde_json::to_string(&WsMessage::Seen(messages_seen)).unwrap())
+                .unwrap();
+        }
+    }
+
+    /// Scrolls to the given message, loading older pages of the history until
+    /// it is found.
+    fn jump_to(&mut self, uuid: Uuid) {
+        if self.received_messages.iter().any(|m| m.uuid == uuid) {
+            self.pending_jump = None;
+            self.scroll_to = Some(uuid);
+        } else if self.has_more_messages {
+            self.pending_jump = Some(uuid);
+            let oldest_message = self.received_messages.last().map(MessageCursor::from);
+            self.request_messages_page(oldest_message);
+        } else {
+            self.pending_jump = None;
+        }
+    }
+
+    /// Retrieves the users connected to the room.
+    fn request_members(&self, ctx: &Context<Self>) {
+        let mut req = Requester::get(&format!("/api/room/{}/members", ctx.props().room));
+        req.bearer(self.bearer.clone());
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let members: Vec<RoomMember> =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(Msg::MembersRetrieved(members));
+            }
+        });
+    }
+
+    fn request_pins(&self, ctx: &Context<Self>) {
+        let mut req = Requester::get(&format!("/api/room/{}/pins", ctx.props().room));
+        req.bearer(self.bearer.clone());
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let pins: Vec<PinnedMessage> =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(Msg::PinsRetrieved(pins));
+            }
+        });
+    }
+
+    /// Retrieves the number of unread messages in the rooms the user follows.
+    fn request_unread_counts(&self, ctx: &Context<Self>) {
+        let mut req = Requester::get("/api/rooms/unread");
+        req.bearer(self.bearer.clone());
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let counts: Vec<UnreadCount> =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(Msg::UnreadCountsRetrieved(counts));
+            }
+        });
+    }
+
+    /// Returns the logins of the users that can be mentioned, which are the
+    /// ones online or who wrote the loaded messages.
+    fn mentionable_logins(&self) -> Vec<String> {
+        let mut logins: Vec<String> = self
+            .members
+            .iter()
+            .map(|member| member.login.clone())
+            .chain(
+                self.received_messages
+                    .iter()
+                    .map(|m| m.author.login.clone()),
+            )
+            .filter(|login| *login != self.user_context.user.login)
+            .collect();
+        logins.sort();
+        logins.dedup();
+        logins
+    }
+
+    /// Returns the line informing which users are currently typing, if any.
+    fn typing_indicator(&self) -> Option<Html> {
+        let translation = &self.user_context.translation;
+        let content = match self.typing_users.as_slice() {
+            [] => return None,
+            [user] => format!(
+                "{} {}",
+                user.name,
+                translation.get_or_default("is_typing", "is typing…")
+            ),
+            [first, second] => format!(
+                "{} {} {} {}",
+                first.name,
+                translation.get_or_default("and", "and"),
+                second.name,
+                translation.get_or_default("are_typing", "are typing…")
+            ),
+            _ => translation.get_or_default("several_typing", "Several people are typing…"),
+        };
+        Some(html! {
+            <div class="sticky bottom-0 text-xs italic text-gray-500 dark:text-gray-400 px-5 py-1">{content}</div>
+        })
+    }
 }
 
 impl Component for Feed {
@@ -72,6 +304,18 @@ impl Component for Feed {
     type Properties = Props;
 
     fn create(ctx: &Context<Self>) -> Self {
+        let user_context = ctx
+            .props()
+            .client_context
+            .user_context
+            .as_ref()
+            .cloned()
+            .expect("Context is defined since AuthGuarded");
+        // The messages written by another user of the browser are left out.
+        let outbox: Vec<WsMessageContent> = outbox::load(&ctx.props().room)
+            .into_iter()
+            .filter(|m| m.author.id == user_context.user.id)
+            .collect();
         let ws: WebsocketService = WebsocketService::new(
             &ctx.props().room,
             ctx.props()
@@ -79,12 +323,13 @@ impl Component for Feed {
                 .bearer
                 .as_ref()
                 .expect("Bearer must be defined since AuthGuarded"),
+            user_context.translation.clone(),
         );
         let cb = {
             let link = ctx.link().clone();
             move |msg| link.send_message(Msg::HandleWsInteraction(Box::new(msg)))
         };
-        Self {
+        let feed = Self {
             received_messages: vec![],
             ws,
             _producer: ChatBus::bridge(Rc::new(cb)),
@@ -98,15 +343,28 @@ impl Component for Feed {
             },
             session_id: Uuid::new_v4(),
             room_name_checked: false,
-            user_context: ctx
-                .props()
-                .client_context
-                .user_context
-                .as_ref()
-                .cloned()
-                .expect("Context is defined since AuthGuarded"),
+            user_context,
             bearer: ctx.props().client_context.bearer.clone(),
-        }
+            chat_ref: NodeRef::default(),
+            has_more_messages: false,
+            is_loading_messages: false,
+            reply_to: None,
+            typing_users: vec![],
+            members: vec![],
+            is_member_list_collapsed: false,
+            pending_jump: None,
+            scroll_to: None,
+            pins: vec![],
+            unread: vec![],
+            last_read: None,
+            unread_retrieved: false,
+            outbox,
+            outbox_retry: None,
+        };
+        // Requested before the messages are marked as seen, so that the last
+        // message read is the one preceding the user's arrival.
+        feed.request_unread_counts(ctx);
+        feed
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
@@ -140,14 +398,23 @@ impl Component for Feed {
                                 }
                             }
                             self.ws_keep_alive = None;
+                            self.typing_users.clear();
+                            self.members.clear();
                             self.is_connected = false;
                         }
                     }
                     WsMessage::Receive(msg_content) => {
-                        self.received_messages.insert(0, msg_content.clone());
-                        if msg_content.reception_status == WsReceptionStatus::Sent
-                            && msg_content.author.id != self.user_context.user.id
+                        self.remove_from_outbox(ctx, msg_content.uuid);
+                        // The message may have been added on acknowledgment.
+                        match self
+                            .received_messages
+                            .iter_mut()
+                            .find(|m| m.uuid == msg_content.uuid)
                         {
+                            Some(msg) => *msg = msg_content.clone(),
+                            None => self.received_messages.insert(0, msg_content.clone()),
+                        }
+                        if self.is_unseen(&msg_content) {
                             self.ws
                                 .tx
                                 .clone()
@@ -159,40 +426,40 @@ impl Component for Feed {
                         }
                     }
                     WsMessage::MessagesRetrieved {
-                        mut messages,
+                        messages,
                         session_id,
                     } if session_id == self.session_id => {
-                        let messages_seen: Vec<Uuid> = messages
-                            .clone()
-                            .into_iter()
-                            .filter(|message| {
-                                message.reception_status == WsReceptionStatus::Sent
-                                    && message.author.id != self.user_context.user.id
-                            })
-                            .map(|m| m.uuid)
-                            .collect();
-                        self.received_messages.append(&mut messages);
-
-                        if !messages_seen.is_empty() {
-                            self.ws
-                                .tx
-                                .clone()
-                                .try_send(
-                                    serde_json::to_string(&WsMessage::Seen(messages_seen)).unwrap(),
-                                )
-                                .unwrap();
+                        self.append_retrieved_messages(messages);
+                    }
+                    WsMessage::MessagesPageRetrieved { page, session_id }
+                        if session_id == self.session_id =>
+                    {
+                        self.is_loading_messages = false;
+                        self.has_more_messages = page.has_more;
+                        self.append_retrieved_messages(page.messages);
+                        if let Some(uuid) = self.pending_jump {
+                            self.jump_to(uuid);
                         }
                     }
+                    WsMessage::Hello { .. } => {
+                        self.is_connected = true;
+                        // The server answers the handshake of this connection
+                        // only, the messages written while disconnected, or
+                        // lost with the previous connection, are sent again.
+                        self.flush_outbox();
+                    }
                     WsMessage::Pong => {
                         self.is_connected = true;
 
                         if self.received_messages.is_empty() {
-                            let msg = WsMessage::RetrieveMessages(self.session_id);
-                            self.ws
-                                .tx
-                                .clone()
-                                .try_send(serde_json::to_string(&msg).unwrap())
-                                .unwrap();
+                            self.request_messages_page(None);
+                        }
+                        // The keep alive is stopped whenever the connection is
+                        // lost, so the room's state is retrieved again once
+                        // it reopens.
+                        if self.ws_keep_alive.is_none() {
+                            self.request_members(ctx);
+                            self.request_pins(ctx);
                             self.ws_keep_alive = {
                                 let tx = self.ws.tx.clone();
                                 Some(Interval::new(30_000, move || {
@@ -201,13 +468,169 @@ impl Component for Feed {
                             }
                         }
                     }
-                    WsMessage::MessagesSeen(msgs_uuid) => {
-                        for mut msg in self.received_messages.iter_mut() {
-                            if msgs_uuid.contains(&msg.uuid) {
-                                msg.reception_status = WsReceptionStatus::Seen;
+                    WsMessage::MessagesSeen {
+                        messages,
+                        user_id,
+                        name,
+                        seen_at,
+                    } => {
+                        for msg in self
+                            .received_messages
+                            .iter_mut()
+                            .filter(|m| messages.contains(&m.uuid))
+                        {
+                            match msg.receipts.iter_mut().find(|r| r.user_id == user_id) {
+                                Some(receipt) => receipt.seen_at = Some(seen_at),
+                                None => msg.receipts.push(MessageReceipt {
+                                    user_id,
+                                    name: name.clone(),
+                                    delivered_at: seen_at,
+                                    seen_at: Some(seen_at),
+                                }),
                             }
                         }
                     }
+                    WsMessage::Edited {
+                        uuid,
+                        content,
+                        edited_at,
+                    } => {
+                        if let Some(msg) =
+                            self.received_messages.iter_mut().find(|m| m.uuid == uuid)
+                        {
+                            msg.content = content.clone();
+                            msg.edited_at = Some(edited_at);
+                        }
+                        for preview in self
+                            .received_messages
+                            .iter_mut()
+                            .filter_map(|m| m.reply_preview.as_mut())
+                            .filter(|preview| preview.uuid == uuid)
+                        {
+                            preview.content =
+                                content.chars().take(PREVIEW_LENGTH as usize).collect();
+                        }
+                        if let Some(pin) = self.pins.iter_mut().find(|pin| pin.uuid == uuid) {
+                            pin.content = content;
+                        }
+                    }
+                    WsMessage::Deleted(messages) => {
+                        for msg in self
+                            .received_messages
+                            .iter_mut()
+                            .filter(|m| messages.contains(&m.uuid))
+                        {
+                            msg.content.clear();
+                            msg.attachments.clear();
+                            msg.deleted_at = Some(chrono::Utc::now());
+                        }
+                        for preview in self
+                            .received_messages
+                            .iter_mut()
+                            .filter_map(|m| m.reply_preview.as_mut())
+                            .filter(|preview| messages.contains(&preview.uuid))
+                        {
+                            preview.content.clear();
+                            preview.is_deleted = true;
+                        }
+                        self.pins.retain(|pin| !messages.contains(&pin.uuid));
+                    }
+                    WsMessage::PinsUpdated(pins) => {
+                        self.pins = pins;
+                    }
+                    WsMessage::ReactionsUpdated { uuid, reactions } => {
+                        if let Some(msg) =
+                            self.received_messages.iter_mut().find(|m| m.uuid == uuid)
+                        {
+                            msg.reactions = reactions;
+                        }
+                    }
+                    WsMessage::UsersTyping(users) => {
+                        self.typing_users = users
+                            .into_iter()
+                            .filter(|user| user.user_id != self.user_context.user.id)
+                            .collect();
+                    }
+                    WsMessage::PresenceChanged(members) => {
+                        self.members = members;
+                    }
+                    WsMessage::Mentioned(message) => {
+                        // The mentions made in the current room are
+                        // highlighted within the chat.
+                        if message.room != ctx.props().room.as_str() {
+                            ToastBus::dispatcher().send(Alert {
+                                is_success: true,
+                                content: format!(
+                                    "{} {} {}",
+                                    message.author.name,
+                                    self.user_context
+                                        .translation
+                                        .get_or_default("mentioned_you_in", "mentioned you in"),
+                                    message.room
+                                ),
+                            });
+                        }
+                    }
+                    WsMessage::UnreadChanged { room, delta } => {
+                        if room == ctx.props().room.as_str() {
+                            return false;
+                        }
+                        match self.unread.iter_mut().find(|count| count.room == room) {
+                            Some(count) => count.unread = (count.unread + delta).max(0),
+                            // The interlocutor of a new direct conversation is
+                            // only known once the counts are retrieved again.
+                            None => self.request_unread_counts(ctx),
+                        }
+                    }
+                    WsMessage::Ack {
+                        uuid,
+                        server_timestamp,
+                    } => {
+                        // The message is usually received before its
+                        // acknowledgment, unless it had already been
+                        // persisted.
+                        if let Some(mut msg) = self.remove_from_outbox(ctx, uuid) {
+                            msg.timestamp = server_timestamp;
+                            msg.reception_status = WsReceptionStatus::Sent;
+                            self.received_messages.insert(0, msg);
+                        }
+                    }
+                    WsMessage::SlowDown { retry_after_ms, .. } => {
+                        // The message is kept, and sent again once the delay
+                        // is over.
+                        self.outbox_retry = {
+                            let link = ctx.link().clone();
+                            Some(Timeout::new(retry_after_ms as u32, move || {
+                                link.send_message(Msg::FlushOutbox)
+                            }))
+                        };
+                        ToastBus::dispatcher().send(Alert {
+                            is_success: false,
+                            content: format!(
+                                "{} ({}s)",
+                                self.user_context.translation.get_or_default(
+                                    "slow_down",
+                                    "You're sending messages too fast, please wait a moment."
+                                ),
+                                retry_after_ms.div_ceil(1000)
+                            ),
+                        });
+                    }
+                    WsMessage::Error(error) => {
+                        if let Some(uuid) = error.refused_message() {
+                            self.remove_from_outbox(ctx, uuid);
+                        }
+                        ToastBus::dispatcher().send(Alert {
+                            is_success: false,
+                            content: error.fill_translation(
+                                &self
+                                    .user_context
+                                    .translation
+                                    .clone()
+                                    .get_or_default(error.label(), &error.to_string()),
+                            ),
+                        });
+                    }
                     _ => {
                         self.is_connected = true;
                     }
@@ -227,6 +650,7 @@ impl Component for Feed {
                 let ws: WebsocketService = WebsocketService::new(
                     &ctx.props().room,
                     ctx.props().client_context.bearer.as_ref().unwrap(),
+                    self.user_context.translation.clone(),
                 );
                 self.ws = ws;
                 self.ws
@@ -245,38 +669,166 @@ impl Component for Feed {
                 });
                 true
             }
+            Msg::ChatScrolled => {
+                if !self.has_more_messages || self.is_loading_messages || !self.is_connected {
+                    return false;
+                }
+                let Some(chat) = self.chat_ref.cast::<Element>() else {
+                    return false;
+                };
+                // The chat is displayed in reverse order, so the scroll top is
+                // negative and decreases as the user scrolls up.
+                let distance_to_top =
+                    chat.scroll_height() - chat.client_height() - chat.scroll_top().abs();
+                if distance_to_top > LOAD_OLDER_MESSAGES_THRESHOLD {
+                    return false;
+                }
+                let oldest_message = self.received_messages.last().map(MessageCursor::from);
+                self.request_messages_page(oldest_message);
+                true
+            }
+            Msg::ReplyTo(reply_to) => {
+                self.reply_to = reply_to;
+                true
+            }
+            Msg::MembersRetrieved(members) => {
+                self.members = members;
+                true
+            }
+            Msg::PinsRetrieved(pins) => {
+                self.pins = pins;
+                true
+            }
+            Msg::ToggleMembers => {
+                self.is_member_list_collapsed = !self.is_member_list_collapsed;
+                true
+            }
+            Msg::JumpTo(uuid) => {
+                self.jump_to(uuid);
+                true
+            }
+            Msg::UnreadCountsRetrieved(counts) => {
+                let room = ctx.props().room.as_str();
+                // The marker moves as the messages are read, so only its
+                // initial position is kept.
+                if !self.unread_retrieved {
+                    self.unread_retrieved = true;
+                    self.last_read = counts
+                        .iter()
+                        .find(|count| count.room == room)
+                        .and_then(|count| count.last_read);
+                }
+                self.unread = counts
+                    .into_iter()
+                    .filter(|count| count.room != room)
+                    .collect();
+                true
+            }
+            Msg::Send(message) => {
+                self.outbox.push(*message);
+                outbox::store(&ctx.props().room, &self.outbox);
+                if self.outbox.len() == 1 {
+                    self.flush_outbox();
+                }
+                true
+            }
+            Msg::FlushOutbox => {
+                self.outbox_retry = None;
+                self.flush_outbox();
+                false
+            }
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let component: Html = match self.is_connected {
-            true => {
-                let tx = self.ws.tx.clone();
-                let pass_message_to_ws = Callback::from(move |message: String| {
-                    tx.clone().try_send(message).unwrap();
-                });
-                html! {<TypeBar translation={self.user_context.translation.clone()} {pass_message_to_ws} user={self.user_context.user.clone()} room={ctx.props().room.clone()}/>}
-            }
-            false => {
-                let link = ctx.link().clone();
-                let try_reconnect = Callback::from(move |_: ()| {
-                    link.send_message(Msg::TryReconnect);
-                });
-                html! {<DisconnectedBar translation={self.user_context.translation.clone()} called_back={self.called_back} {try_reconnect} />}
+        let tx = self.ws.tx.clone();
+        // The messages can't be sent while disconnected, the ones written
+        // being kept in the outbox.
+        let pass_message_to_ws = Callback::from(move |message: String| {
+            let _ = tx.clone().try_send(message);
+        });
+        let reply_to = self.reply_to.as_ref().map(|preview| preview.uuid);
+        let cancel_reply = ctx.link().callback(|_| Msg::ReplyTo(None));
+        let logins = self.mentionable_logins();
+        let on_send = ctx.link().callback(|msg| Msg::Send(Box::new(msg)));
+        let disconnected_bar: Option<Html> = (!self.is_connected).then(|| {
+            let link = ctx.link().clone();
+            let try_reconnect = Callback::from(move |_: ()| {
+                link.send_message(Msg::TryReconnect);
+            });
+            html! {
+                <div class="sticky bottom-0 bg-gray-100 dark:bg-zinc-900 px-5 py-2">
+                    <DisconnectedBar translation={self.user_context.translation.clone()} called_back={self.called_back} {try_reconnect} />
+                </div>
             }
+        });
+        let loading_messages: Option<Html> = match self.is_loading_messages {
+            true => Some(html! { <div class="py-2"><WaitingForResponse /></div> }),
+            false => None,
         };
-        html! {
-            <div class="grid grid-rows-11 h-full dark:bg-zinc-800">
-                <div class="row-span-10 overflow-auto flex flex-col-reverse" >
-                    <Chat messages={self.received_messages.clone()} room={ctx.props().room.clone()} user={self.user_context.user.clone()} />
+        let reply_banner: Option<Html> = self.reply_to.as_ref().map(|preview| {
+            let translation = &self.user_context.translation;
+            html! {
+                <div class="sticky bottom-0 flex justify-between items-center text-sm bg-gray-200 dark:bg-zinc-700 dark:text-gray-200 px-5 py-1">
+                    <span class="truncate">
+                        {translation.get_or_default("replying_to", "Replying to")}{" "}
+                        <span class="font-semibold">{&preview.author_name}</span>{" : "}{&preview.content}
+                    </span>
+                    <button class="ml-2 underline" onclick={ctx.link().callback(|_| Msg::ReplyTo(None))}>{translation.get_or_default("cancel_reply", "Cancel")}</button>
                 </div>
-                <div class="row-span-1 grid grid-cols-6 px-5 gap-4 justify-center content-center block">
-                    {component}
+            }
+        });
+        let on_reply = ctx.link().callback(|preview| Msg::ReplyTo(Some(preview)));
+        let on_toggle_members = ctx.link().callback(|_| Msg::ToggleMembers);
+        let on_unpin = can_pin(&ctx.props().room, &self.user_context.user).then(|| {
+            let pass_message_to_ws = pass_message_to_ws.clone();
+            Callback::from(move |uuid| {
+                pass_message_to_ws.emit(serde_json::to_string(&WsMessage::Unpin(uuid)).unwrap())
+            })
+        });
+        let pinned: Vec<Uuid> = self.pins.iter().map(|pin| pin.uuid).collect();
+        // The messages waiting for an acknowledgment are displayed as the most
+        // recent ones.
+        let messages: Vec<WsMessageContent> = self
+            .outbox
+            .iter()
+            .rev()
+            .chain(self.received_messages.iter())
+            .cloned()
+            .collect();
+        html! {
+            <div class="flex h-full dark:bg-zinc-800">
+                <div class="grid grid-rows-11 h-full flex-grow">
+                    <div class="row-span-10 overflow-auto flex flex-col-reverse" ref={self.chat_ref.clone()} onscroll={ctx.link().callback(|_| Msg::ChatScrolled)} >
+                        {disconnected_bar}
+                        {self.typing_indicator()}
+                        {reply_banner}
+                        <Chat {messages} room={ctx.props().room.clone()} user={self.user_context.user.clone()} pass_message_to_ws={pass_message_to_ws.clone()} {on_reply} {pinned} last_read={self.last_read} />
+                        {loading_messages}
+                        <SearchPanel room={ctx.props().room.clone()} on_select={ctx.link().callback(Msg::JumpTo)} />
+                        <PinnedBanner pins={self.pins.clone()} translation={self.user_context.translation.clone()} on_select={ctx.link().callback(Msg::JumpTo)} {on_unpin} />
+                        <UnreadBanner counts={self.unread.clone()} translation={self.user_context.translation.clone()} />
+                    </div>
+                    <div class="row-span-1 grid grid-cols-6 px-5 gap-4 justify-center content-center block">
+                        <TypeBar translation={self.user_context.translation.clone()} {pass_message_to_ws} {on_send} user={self.user_context.user.clone()} room={ctx.props().room.clone()} {reply_to} {cancel_reply} {logins} bearer={self.bearer.clone()}/>
+                    </div>
                 </div>
+                <MemberList members={self.members.clone()} translation={self.user_context.translation.clone()} is_collapsed={self.is_member_list_collapsed} on_toggle={on_toggle_members} />
             </div>
         }
     }
 
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        if let Some(uuid) = self.scroll_to.take() {
+            if let Some(element) = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.get_element_by_id(&uuid.to_string()))
+            {
+                element.scroll_into_view();
+            }
+        }
+    }
+
     fn destroy(&mut self, ctx: &Context<Self>) {
         self.ws
             .tx
@@ -1,6 +1,7 @@
 // Copyright ⓒ 2022 LABEYE Loïc
 // This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
 
+use crate::components::direct_message::DirectConversationsHOC;
 use crate::router::Route;
 use crate::{components::common::FormButton, utils::client_context::ClientContext};
 use std::rc::Rc;
@@ -79,7 +80,7 @@ impl Component for JoinRoom {
     fn view(&self, ctx: &Context<Self>) -> Html {
         html! {
             <>
-                <div class="flex items-center justify-center h-full dark:bg-zinc-800">
+                <div class="flex flex-col items-center justify-center h-full dark:bg-zinc-800">
                 <form class="w-full max-w-sm border-2 dark:border-zinc-700 px-6 py-6 lg:py-14" onsubmit={ctx.link().callback(|_| Msg::SubmitForm)} action="javascript:void(0);">
 
                 <h2 class="text-xl mb-10 text-center text-gray-500 dark:text-gray-200 font-bold">
@@ -100,6 +101,7 @@ impl Component for JoinRoom {
                     </small>
                   <FormButton label={ctx.props().user_context.translation.as_ref().get_or_default("join_room", "Join")} />
                 </form>
+                <DirectConversationsHOC />
                 </div>
             </>
         }
@@ -0,0 +1,52 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use std::rc::Rc;
+
+use super::chat::DEFAULT_PFP;
+use crate::router::Route;
+use tchatchers_core::room::RoomMember;
+use tchatchers_core::translation::Translation;
+use yew::{function_component, html, Callback, Html, Properties};
+use yew_router::prelude::Link;
+
+#[derive(Properties, PartialEq)]
+pub struct MemberListProperties {
+    pub members: Vec<RoomMember>,
+    pub translation: Rc<Translation>,
+    pub is_collapsed: bool,
+    pub on_toggle: Callback<()>,
+}
+
+#[function_component(MemberList)]
+pub fn member_list(props: &MemberListProperties) -> Html {
+    let on_toggle = props.on_toggle.clone();
+    let onclick = move |_| on_toggle.emit(());
+    let title = format!(
+        "{} ({})",
+        props.translation.get_or_default("online_members", "Online"),
+        props.members.len()
+    );
+    if props.is_collapsed {
+        return html! {
+            <aside class="border-l dark:border-zinc-700 dark:text-gray-200 px-2 py-2">
+                <button class="text-sm font-semibold [writing-mode:vertical-rl]" {onclick}>{title}</button>
+            </aside>
+        };
+    }
+    html! {
+        <aside class="w-56 border-l dark:border-zinc-700 dark:text-gray-200 overflow-auto px-3 py-2">
+            <button class="text-sm font-semibold mb-2" {onclick}>{title}</button>
+            <ul>
+                { props.members.iter().map(|member| html! {
+                    <li class="py-1" title={member.login.clone()}>
+                        <Link<Route> to={Route::DirectMessage { login: member.login.clone() }} classes="flex items-center gap-2">
+                            <img class="h-8 w-8 rounded-full bg-gray-300" src={member.pfp.clone().unwrap_or_else(|| DEFAULT_PFP.into())} alt="No img"/>
+                            <span class="truncate">{&member.name}</span>
+                        </Link<Route>>
+                    </li>
+                }).collect::<Html>() }
+            </ul>
+        </aside>
+    }
+}
@@ -4,22 +4,28 @@
 pub mod auth_guard;
 pub mod chat;
 pub mod common;
+pub mod direct_message;
 pub mod disconnected_bar;
 pub mod feed;
 pub mod join_room;
 pub mod logout;
+pub mod member_list;
 pub mod modal;
 pub mod navbar;
 pub mod navlink;
 pub mod not_found;
+pub mod pinned_banner;
+pub mod search_panel;
 pub mod settings;
 pub mod signin;
 pub mod signup;
 pub mod toast;
 pub mod type_bar;
+pub mod unread_banner;
 
 pub mod prelude {
     pub use super::auth_guard::AuthGuard;
+    pub use super::direct_message::DirectMessageHOC;
     pub use super::feed::FeedHOC;
     pub use super::join_room::JoinRoomHOC;
     pub use super::logout::LogOut;
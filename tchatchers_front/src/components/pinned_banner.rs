@@ -0,0 +1,67 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use std::rc::Rc;
+
+use tchatchers_core::pin::PinnedMessage;
+use tchatchers_core::translation::Translation;
+use uuid::Uuid;
+use yew::{function_component, html, use_state, Callback, Html, Properties};
+
+#[derive(Properties, PartialEq)]
+pub struct PinnedBannerProperties {
+    pub pins: Vec<PinnedMessage>,
+    pub translation: Rc<Translation>,
+    pub on_select: Callback<Uuid>,
+    #[prop_or_default]
+    pub on_unpin: Option<Callback<Uuid>>,
+}
+
+/// Displays the most recently pinned message of the room, the other ones
+/// being listed once expanded.
+#[function_component(PinnedBanner)]
+pub fn pinned_banner(props: &PinnedBannerProperties) -> Html {
+    let is_expanded = use_state(|| false);
+    if props.pins.is_empty() {
+        return html! {};
+    }
+    let pins: &[PinnedMessage] = match *is_expanded {
+        true => &props.pins,
+        false => &props.pins[..1],
+    };
+    let toggle = {
+        let is_expanded = is_expanded.clone();
+        move |_| is_expanded.set(!*is_expanded)
+    };
+    let title = format!(
+        "{} ({})",
+        props
+            .translation
+            .get_or_default("pinned_messages", "Pinned messages"),
+        props.pins.len()
+    );
+    html! {
+        <div class="sticky top-0 z-10 bg-gray-100 dark:bg-zinc-900 dark:text-gray-200 border-b dark:border-zinc-700 px-5 py-1 text-sm">
+            <button class="text-xs font-semibold" onclick={toggle}>{title}</button>
+            <ul>
+                { pins.iter().map(|pin| {
+                    let uuid = pin.uuid;
+                    let on_select = props.on_select.clone();
+                    let unpin_button = props.on_unpin.clone().map(|on_unpin| html! {
+                        <button class="ml-2 text-xs underline shrink-0" onclick={move |_| on_unpin.emit(uuid)}>
+                            {props.translation.get_or_default("unpin_message", "Unpin")}
+                        </button>
+                    });
+                    html! {
+                        <li class="flex justify-between items-center" title={format!("{} {}", props.translation.get_or_default("pinned_by", "Pinned by"), pin.pinned_by)}>
+                            <span class="truncate cursor-pointer" onclick={move |_| on_select.emit(uuid)}>
+                                <span class="font-semibold">{&pin.author_name}</span>{" : "}{&pin.content}
+                            </span>
+                            {unpin_button}
+                        </li>
+                    }
+                }).collect::<Html>() }
+            </ul>
+        </div>
+    }
+}
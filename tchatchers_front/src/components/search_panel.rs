@@ -0,0 +1,102 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use std::rc::Rc;
+
+use crate::utils::client_context::ClientContext;
+use crate::utils::requester::Requester;
+use tchatchers_core::ws_message::WsMessageContent;
+use uuid::Uuid;
+use web_sys::{HtmlInputElement, SubmitEvent};
+use yew::{
+    function_component, html, use_context, use_node_ref, use_state, AttrValue, Callback, Html,
+    Properties,
+};
+
+#[derive(Properties, PartialEq)]
+pub struct SearchPanelProperties {
+    pub room: AttrValue,
+    pub on_select: Callback<Uuid>,
+}
+
+/// Searches the messages of the room, and lets the user jump to one of them.
+#[function_component(SearchPanel)]
+pub fn search_panel(props: &SearchPanelProperties) -> Html {
+    let client_context = use_context::<Rc<ClientContext>>().unwrap();
+    let translation = client_context
+        .user_context
+        .as_ref()
+        .map(|user_context| user_context.translation.clone())
+        .unwrap_or_default();
+    let results = use_state(|| None::<Vec<WsMessageContent>>);
+    let search_input = use_node_ref();
+
+    let onsubmit = {
+        let results = results.clone();
+        let bearer = client_context.bearer.clone();
+        let input_ref = search_input.clone();
+        let room = props.room.clone();
+        move |se: SubmitEvent| {
+            se.prevent_default();
+            let Some(input) = input_ref.cast::<HtmlInputElement>() else {
+                return;
+            };
+            if !input.check_validity() || input.value().is_empty() {
+                results.set(None);
+                return;
+            }
+            let mut req = Requester::get(&format!(
+                "/api/search?q={}&room={}",
+                js_sys::encode_uri_component(&input.value()),
+                js_sys::encode_uri_component(&room)
+            ));
+            req.bearer(bearer.clone());
+            let results = results.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let resp = req.send().await;
+                if resp.ok() {
+                    let messages: Vec<WsMessageContent> =
+                        serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                    results.set(Some(messages));
+                }
+            });
+        }
+    };
+
+    let result_list = (*results).as_ref().map(|messages| {
+        let items: Html = match messages.is_empty() {
+            true => html! {
+                <li class="italic opacity-75">{translation.get_or_default("no_search_result", "No message found")}</li>
+            },
+            false => messages
+                .iter()
+                .map(|message| {
+                    let uuid = message.uuid;
+                    let on_select = props.on_select.clone();
+                    let results = results.clone();
+                    let onclick = move |_| {
+                        on_select.emit(uuid);
+                        results.set(None);
+                    };
+                    html! {
+                        <li class="truncate cursor-pointer hover:bg-gray-300 dark:hover:bg-zinc-600 px-1" {onclick}>
+                            <span class="font-semibold">{&message.author.name}</span>{" : "}{&message.content}
+                        </li>
+                    }
+                })
+                .collect(),
+        };
+        html! {
+            <ul class="text-sm max-h-48 overflow-auto mt-1">{items}</ul>
+        }
+    });
+
+    html! {
+        <div class="sticky top-0 z-10 bg-gray-200 dark:bg-zinc-700 dark:text-gray-200 px-5 py-1">
+            <form {onsubmit} action="javascript:void(0);">
+                <input class="rounded px-2 w-full text-gray-700 invalid:border-red-500" type="search" minlength="1" maxlength="127" placeholder={translation.get_or_default("search_messages", "Search messages")} ref={search_input} />
+            </form>
+            {result_list}
+        </div>
+    }
+}
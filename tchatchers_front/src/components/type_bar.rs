@@ -3,67 +3,481 @@
 
 use std::rc::Rc;
 
+use super::chat::{format_file_size, format_timestamp};
+use super::toast::Alert;
+use crate::services::toast_bus::ToastBus;
+use crate::utils::requester::Requester;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use tchatchers_core::attachment::{Attachment, MAX_ATTACHMENTS_PER_MESSAGE, MAX_ATTACHMENT_SIZE};
+use tchatchers_core::scheduled_message::{
+    InsertableScheduledMessage, ScheduledMessage, MAX_SCHEDULE_DAYS,
+};
 use tchatchers_core::translation::Translation;
-use tchatchers_core::ws_message::WsMessage;
+use tchatchers_core::ws_message::{WsMessage, TYPING_REFRESH_SECONDS};
 use tchatchers_core::{user::PartialUser, ws_message::WsMessageContent};
+use uuid::Uuid;
 use web_sys::HtmlInputElement;
-use yew::{html, AttrValue, Callback, Component, Context, Html, NodeRef, Properties};
+use yew::{
+    html, AttrValue, Callback, Component, Context, Html, NodeRef, Properties, UseStateHandle,
+};
+use yew_agent::Dispatched;
 
 pub enum Msg {
     SubmitForm,
+    InputChanged,
+    CompleteMention(String),
+    FilesSelected,
+    AttachmentUploaded(Attachment),
+    UploadFailed(String),
+    RemoveAttachment(Uuid),
+    ToggleScheduling,
+    ScheduledMessagesFetched(Vec<ScheduledMessage>),
+    MessageScheduled(ScheduledMessage),
+    ScheduleFailed(String),
+    CancelScheduled(Uuid),
+    ScheduledCancelled(Uuid),
 }
 
+/// The maximum number of logins suggested when mentioning a user.
+const MAX_MENTION_SUGGESTIONS: usize = 5;
+
+/// The format of the values of the inputs picking a local date and time.
+const DATETIME_LOCAL_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
 #[derive(Clone, PartialEq, Properties)]
 pub struct Props {
     pub pass_message_to_ws: Callback<String>,
+    /// Called with the messages written by the user.
+    pub on_send: Callback<WsMessageContent>,
     pub room: AttrValue,
     pub user: PartialUser,
     pub translation: Rc<Translation>,
+    #[prop_or_default]
+    pub reply_to: Option<Uuid>,
+    pub cancel_reply: Callback<()>,
+    /// The logins that can be suggested when mentioning a user.
+    #[prop_or_default]
+    pub logins: Vec<String>,
+    pub bearer: UseStateHandle<Option<String>>,
 }
 
 #[derive(Default)]
 pub struct TypeBar {
     input_ref: NodeRef,
+    /// The last time the room has been informed the user is typing.
+    last_typing: Option<DateTime<Utc>>,
+    /// The logins matching the mention being typed.
+    suggestions: Vec<String>,
+    file_input_ref: NodeRef,
+    /// The files uploaded, joined to the next message sent.
+    attachments: Vec<Attachment>,
+    /// The number of files being uploaded.
+    pending_uploads: usize,
+    /// Whether the next message is scheduled instead of being sent.
+    is_scheduling: bool,
+    schedule_ref: NodeRef,
+    /// The messages the user scheduled in the room.
+    scheduled: Vec<ScheduledMessage>,
+}
+
+impl TypeBar {
+    /// Informs the room whether the user is typing, at most once per refresh
+    /// period.
+    fn notify_typing(&mut self, ctx: &Context<Self>, is_empty: bool) {
+        let now = Utc::now();
+        let msg = match (is_empty, self.last_typing) {
+            (true, Some(_)) => {
+                self.last_typing = None;
+                WsMessage::StoppedTyping
+            }
+            (true, None) => return,
+            (false, Some(last_typing))
+                if now - last_typing < Duration::seconds(TYPING_REFRESH_SECONDS as i64) =>
+            {
+                return
+            }
+            (false, _) => {
+                self.last_typing = Some(now);
+                WsMessage::Typing
+            }
+        };
+        ctx.props()
+            .pass_message_to_ws
+            .emit(serde_json::to_string(&msg).unwrap());
+    }
+
+    /// Uploads a file, so that it can be joined to the next message.
+    fn upload(ctx: &Context<Self>, file: web_sys::File) {
+        let mut req = Requester::post(&format!(
+            "/api/attachment?name={}",
+            js_sys::encode_uri_component(&file.name())
+        ));
+        req.bearer(ctx.props().bearer.clone());
+        req.body(Some(file));
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let attachment: Attachment =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(Msg::AttachmentUploaded(attachment));
+            } else {
+                link.send_message(Msg::UploadFailed(resp.text().await.unwrap()));
+            }
+        });
+    }
+
+    /// Displays an error to the user.
+    fn alert(content: String) {
+        ToastBus::dispatcher().send(Alert {
+            is_success: false,
+            content,
+        });
+    }
+
+    /// Retrieves the messages the user scheduled in the room.
+    fn fetch_scheduled(ctx: &Context<Self>) {
+        let mut req = Requester::get(&format!("/api/room/{}/scheduled", ctx.props().room));
+        req.bearer(ctx.props().bearer.clone());
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let scheduled: Vec<ScheduledMessage> =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(Msg::ScheduledMessagesFetched(scheduled));
+            }
+        });
+    }
+
+    /// Schedules a message to be sent in the room.
+    fn schedule(ctx: &Context<Self>, message: InsertableScheduledMessage) {
+        let mut req = Requester::post(&format!("/api/room/{}/scheduled", ctx.props().room));
+        req.bearer(ctx.props().bearer.clone())
+            .is_json(true)
+            .json_body(message);
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let resp = req.send().await;
+            if resp.ok() {
+                let scheduled: ScheduledMessage =
+                    serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+                link.send_message(Msg::MessageScheduled(scheduled));
+            } else {
+                link.send_message(Msg::ScheduleFailed(resp.text().await.unwrap()));
+            }
+        });
+    }
+
+    /// Returns the date picked by the user, converted from their timezone.
+    fn picked_send_at(&self, ctx: &Context<Self>) -> Option<DateTime<Utc>> {
+        let value = self.schedule_ref.cast::<HtmlInputElement>()?.value();
+        let local = NaiveDateTime::parse_from_str(&value, DATETIME_LOCAL_FORMAT).ok()?;
+        Some(Utc.from_utc_datetime(&local) - Duration::seconds(ctx.props().user.timezone.tz_offset))
+    }
+
+    /// Returns the logins matching the mention ending the input, if any.
+    fn find_suggestions(ctx: &Context<Self>, value: &str) -> Vec<String> {
+        let last_word = value.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let Some(prefix) = last_word.strip_prefix('@') else {
+            return vec![];
+        };
+        let prefix = prefix.to_lowercase();
+        ctx.props()
+            .logins
+            .iter()
+            .filter(|login| login.to_lowercase().starts_with(&prefix))
+            .take(MAX_MENTION_SUGGESTIONS)
+            .cloned()
+            .collect()
+    }
 }
 
 impl Component for TypeBar {
     type Message = Msg;
     type Properties = Props;
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        Self::fetch_scheduled(ctx);
         Self::default()
     }
 
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().room != old_props.room {
+            self.scheduled.clear();
+            Self::fetch_scheduled(ctx);
+        }
+        true
+    }
+
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::SubmitForm => {
                 if let Some(input) = self.input_ref.cast::<HtmlInputElement>() {
-                    if !input.check_validity() || input.value().is_empty() {
+                    if !input.check_validity()
+                        || (input.value().is_empty() && self.attachments.is_empty())
+                        || self.pending_uploads > 0
+                    {
+                        return false;
+                    }
+                    if self.is_scheduling {
+                        if input.value().is_empty() {
+                            return false;
+                        }
+                        let Some(send_at) = self.picked_send_at(ctx) else {
+                            Self::alert(ctx.props().translation.get_or_default(
+                                "pick_send_date",
+                                "Pick when the message has to be sent.",
+                            ));
+                            return false;
+                        };
+                        Self::schedule(
+                            ctx,
+                            InsertableScheduledMessage {
+                                content: input.value(),
+                                reply_to: ctx.props().reply_to,
+                                send_at,
+                            },
+                        );
                         return false;
                     }
                     let msg = WsMessageContent {
                         room: ctx.props().room.to_string(),
                         author: ctx.props().user.clone(),
                         content: input.value(),
+                        reply_to: ctx.props().reply_to,
+                        attachments: self.attachments.drain(..).collect(),
                         ..WsMessageContent::default()
                     };
-                    ctx.props()
-                        .pass_message_to_ws
-                        .emit(serde_json::to_string(&WsMessage::Send(msg)).unwrap());
+                    ctx.props().on_send.emit(msg);
                     input.set_value("");
+                    self.suggestions.clear();
+                    // The server stops the typing indicator once the message
+                    // is received.
+                    self.last_typing = None;
+                    if ctx.props().reply_to.is_some() {
+                        ctx.props().cancel_reply.emit(());
+                    }
                 }
 
                 true
             }
+            Msg::InputChanged => {
+                let Some(input) = self.input_ref.cast::<HtmlInputElement>() else {
+                    return false;
+                };
+                let value = input.value();
+                self.notify_typing(ctx, value.is_empty());
+                let suggestions = Self::find_suggestions(ctx, &value);
+                let has_changed = suggestions != self.suggestions;
+                self.suggestions = suggestions;
+                has_changed
+            }
+            Msg::CompleteMention(login) => {
+                if let Some(input) = self.input_ref.cast::<HtmlInputElement>() {
+                    let value = input.value();
+                    // The whitespace preceding the mention may be longer
+                    // than a byte, such as a no-break space.
+                    let start = value
+                        .char_indices()
+                        .rev()
+                        .find(|(_, c)| c.is_whitespace())
+                        .map(|(index, c)| index + c.len_utf8())
+                        .unwrap_or(0);
+                    input.set_value(&format!("{}@{login} ", &value[..start]));
+                    let _ = input.focus();
+                }
+                self.suggestions.clear();
+                true
+            }
+            Msg::FilesSelected => {
+                let Some(file_input) = self.file_input_ref.cast::<HtmlInputElement>() else {
+                    return false;
+                };
+                let Some(files) = file_input.files() else {
+                    return false;
+                };
+                let translation = &ctx.props().translation;
+                for file in (0..files.length()).filter_map(|index| files.get(index)) {
+                    if self.attachments.len() + self.pending_uploads >= MAX_ATTACHMENTS_PER_MESSAGE
+                    {
+                        Self::alert(translation.get_or_default(
+                            "too_many_attachments",
+                            "Too many files are attached to the message.",
+                        ));
+                        break;
+                    } else if file.size() > MAX_ATTACHMENT_SIZE as f64 {
+                        Self::alert(
+                            translation
+                                .get_or_default("attachment_too_large", "The file is too large."),
+                        );
+                    } else {
+                        self.pending_uploads += 1;
+                        Self::upload(ctx, file);
+                    }
+                }
+                // Allows the same file to be selected again.
+                file_input.set_value("");
+                true
+            }
+            Msg::AttachmentUploaded(attachment) => {
+                self.pending_uploads -= 1;
+                self.attachments.push(attachment);
+                true
+            }
+            Msg::UploadFailed(reason) => {
+                self.pending_uploads -= 1;
+                Self::alert(reason);
+                true
+            }
+            Msg::RemoveAttachment(id) => {
+                self.attachments.retain(|attachment| attachment.id != id);
+                true
+            }
+            Msg::ToggleScheduling => {
+                self.is_scheduling = !self.is_scheduling;
+                true
+            }
+            Msg::ScheduledMessagesFetched(scheduled) => {
+                self.scheduled = scheduled;
+                true
+            }
+            Msg::MessageScheduled(scheduled) => {
+                if let Some(input) = self.input_ref.cast::<HtmlInputElement>() {
+                    input.set_value("");
+                }
+                self.notify_typing(ctx, true);
+                self.suggestions.clear();
+                self.is_scheduling = false;
+                if ctx.props().reply_to.is_some() {
+                    ctx.props().cancel_reply.emit(());
+                }
+                let position = self
+                    .scheduled
+                    .partition_point(|other| other.send_at <= scheduled.send_at);
+                self.scheduled.insert(position, scheduled);
+                ToastBus::dispatcher().send(Alert {
+                    is_success: true,
+                    content: ctx
+                        .props()
+                        .translation
+                        .get_or_default("message_scheduled", "Your message has been scheduled."),
+                });
+                true
+            }
+            Msg::ScheduleFailed(reason) => {
+                Self::alert(reason);
+                false
+            }
+            Msg::CancelScheduled(uuid) => {
+                let mut req = Requester::delete(&format!("/api/scheduled/{uuid}"));
+                req.bearer(ctx.props().bearer.clone());
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let resp = req.send().await;
+                    if resp.ok() {
+                        link.send_message(Msg::ScheduledCancelled(uuid));
+                    } else {
+                        link.send_message(Msg::ScheduleFailed(resp.text().await.unwrap()));
+                    }
+                });
+                false
+            }
+            Msg::ScheduledCancelled(uuid) => {
+                self.scheduled.retain(|scheduled| scheduled.uuid != uuid);
+                true
+            }
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let suggestions = (!self.suggestions.is_empty()).then(|| {
+            html! {
+                <ul class="rounded-lg shadow bg-gray-200 dark:bg-zinc-700 dark:text-gray-200 text-sm">
+                    {self.suggestions.iter().map(|login| {
+                        let completed_login = login.clone();
+                        html! {
+                            <li class="px-3 py-1 cursor-pointer hover:bg-gray-300 dark:hover:bg-zinc-600" onclick={ctx.link().callback(move |_| Msg::CompleteMention(completed_login.clone()))}>{"@"}{login}</li>
+                        }
+                    }).collect::<Html>()}
+                </ul>
+            }
+        });
+        let attachments = (!self.attachments.is_empty() || self.pending_uploads > 0).then(|| {
+            html! {
+                <ul class="flex flex-wrap gap-1 text-xs dark:text-gray-200">
+                    {self.attachments.iter().map(|attachment| {
+                        let id = attachment.id;
+                        html! {
+                            <li class="flex items-center gap-1 rounded-lg bg-gray-200 dark:bg-zinc-700 px-2 py-1">
+                                <span class="truncate max-w-[10rem]">{&attachment.file_name}</span>
+                                <span class="opacity-75">{format_file_size(attachment.size)}</span>
+                                <button type="button" onclick={ctx.link().callback(move |_| Msg::RemoveAttachment(id))}>{"✕"}</button>
+                            </li>
+                        }
+                    }).collect::<Html>()}
+                    {(0..self.pending_uploads).map(|_| html! {
+                        <li class="rounded-lg bg-gray-200 dark:bg-zinc-700 px-2 py-1 animate-pulse">{"…"}</li>
+                    }).collect::<Html>()}
+                </ul>
+            }
+        });
+        let user_offset = Duration::seconds(ctx.props().user.timezone.tz_offset);
+        let now = Utc::now();
+        // The messages already sent are only removed once the list is fetched
+        // again.
+        let pending: Vec<&ScheduledMessage> = self
+            .scheduled
+            .iter()
+            .filter(|scheduled| scheduled.send_at > now)
+            .collect();
+        let pending = (!pending.is_empty()).then(|| {
+            html! {
+                <ul class="rounded-lg bg-gray-200 dark:bg-zinc-700 dark:text-gray-200 text-xs px-2 py-1" title={ctx.props().translation.get_or_default("scheduled_messages", "Scheduled messages")}>
+                    {pending.iter().map(|scheduled| {
+                        let uuid = scheduled.uuid;
+                        html! {
+                            <li class="flex items-center gap-2">
+                                <span class="opacity-75">{format_timestamp(&(scheduled.send_at + user_offset))}</span>
+                                <span class="truncate max-w-[12rem]">{&scheduled.content}</span>
+                                <button type="button" title={ctx.props().translation.get_or_default("cancel_scheduled", "Cancel")} onclick={ctx.link().callback(move |_| Msg::CancelScheduled(uuid))}>{"✕"}</button>
+                            </li>
+                        }
+                    }).collect::<Html>()}
+                </ul>
+            }
+        });
+        let schedule_input = self.is_scheduling.then(|| {
+            let min = (now + user_offset).format(DATETIME_LOCAL_FORMAT).to_string();
+            let max = (now + user_offset + Duration::days(MAX_SCHEDULE_DAYS))
+                .format(DATETIME_LOCAL_FORMAT)
+                .to_string();
+            html! {
+                <input class="shadow appearance-none border dark:border-zinc-800 rounded-xl px-2 text-gray-700 leading-tight focus:outline-none h-10 bg-gray-200 dark:bg-zinc-700 dark:text-gray-200" type="datetime-local" {min} {max} required=true ref={self.schedule_ref.clone()} />
+            }
+        });
         html! {
             <>
-                <div class="col-span-6 mb-6">
-                <form onsubmit={ctx.link().callback(|_| Msg::SubmitForm)} action="javascript:void(0);">
-                      <input class="shadow appearance-none border dark:border-zinc-800 rounded-xl px-3 text-gray-700 leading-tight focus:outline-none focus:shadow-outline focus:border-zinc-900 w-full h-10 invalid:border-red-500 disabled:bg-gray-100 dark:disabled:bg-zinc-700 focus:invalid:border-red-500 bg-gray-200 dark:bg-zinc-700 dark:text-gray-200" type="text" placeholder={ctx.props().translation.as_ref().clone().get_or_default("type_msg_here", "Type a message here")} minlength="2" maxlength="127" ref={self.input_ref.clone()} />
+                <div class="col-span-6 mb-6 relative">
+                <div class="absolute bottom-full mb-1 flex flex-col gap-1">
+                    {pending}
+                    {suggestions}
+                    {attachments}
+                </div>
+                <form class="flex items-center gap-2" onsubmit={ctx.link().callback(|_| Msg::SubmitForm)} action="javascript:void(0);">
+                      <label class="cursor-pointer dark:text-gray-200" title={ctx.props().translation.get_or_default("attach_file", "Attach a file")}>
+                        <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-6 h-6">
+                            <path stroke-linecap="round" stroke-linejoin="round" d="M15.172 7l-6.586 6.586a2 2 0 102.828 2.828l6.414-6.586a4 4 0 00-5.656-5.656l-6.415 6.585a6 6 0 108.486 8.486L20.5 13" />
+                        </svg>
+                        <input class="hidden" type="file" multiple=true ref={self.file_input_ref.clone()} onchange={ctx.link().callback(|_| Msg::FilesSelected)} />
+                      </label>
+                      <button type="button" class={if self.is_scheduling { "text-sky-600 dark:text-sky-400" } else { "dark:text-gray-200" }} title={ctx.props().translation.get_or_default("send_later", "Send later")} onclick={ctx.link().callback(|_| Msg::ToggleScheduling)}>
+                        <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-6 h-6">
+                            <path stroke-linecap="round" stroke-linejoin="round" d="M12 6v6h4.5m4.5 0a9 9 0 11-18 0 9 9 0 0118 0z" />
+                        </svg>
+                      </button>
+                      {schedule_input}
+                      <input class="shadow appearance-none border dark:border-zinc-800 rounded-xl px-3 text-gray-700 leading-tight focus:outline-none focus:shadow-outline focus:border-zinc-900 w-full h-10 invalid:border-red-500 disabled:bg-gray-100 dark:disabled:bg-zinc-700 focus:invalid:border-red-500 bg-gray-200 dark:bg-zinc-700 dark:text-gray-200" type="text" placeholder={ctx.props().translation.as_ref().clone().get_or_default("type_msg_here", "Type a message here")} minlength="2" maxlength="127" ref={self.input_ref.clone()} oninput={ctx.link().callback(|_| Msg::InputChanged)} />
                       <button type="submit" hidden=true></button>
                   </form>
                 </div>
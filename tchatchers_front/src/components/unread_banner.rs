@@ -0,0 +1,45 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+use std::rc::Rc;
+
+use crate::router::Route;
+use tchatchers_core::read_marker::UnreadCount;
+use tchatchers_core::translation::Translation;
+use yew::{function_component, html, Html, Properties};
+use yew_router::prelude::Link;
+
+#[derive(Properties, PartialEq)]
+pub struct UnreadBannerProperties {
+    pub counts: Vec<UnreadCount>,
+    pub translation: Rc<Translation>,
+}
+
+/// Lists the other rooms in which the user has unread messages.
+#[function_component(UnreadBanner)]
+pub fn unread_banner(props: &UnreadBannerProperties) -> Html {
+    let counts: Vec<&UnreadCount> = props
+        .counts
+        .iter()
+        .filter(|count| count.unread > 0)
+        .collect();
+    if counts.is_empty() {
+        return html! {};
+    }
+    html! {
+        <div class="flex flex-wrap items-center gap-2 bg-gray-100 dark:bg-zinc-900 dark:text-gray-200 border-b dark:border-zinc-700 px-5 py-1 text-xs">
+            <span class="font-semibold">{props.translation.get_or_default("unread_elsewhere", "Unread")}</span>
+            { counts.iter().map(|count| {
+                let (to, name) = match &count.interlocutor {
+                    Some(login) => (Route::DirectMessage { login: login.clone() }, format!("@{login}")),
+                    None => (Route::Room { room: count.room.clone() }, count.room.clone()),
+                };
+                html! {
+                    <Link<Route> {to} classes="rounded-lg bg-gray-200 dark:bg-zinc-700 px-2">
+                        {name}{" "}<span class="font-semibold">{count.unread}</span>
+                    </Link<Route>>
+                }
+            }).collect::<Html>() }
+        </div>
+    }
+}
@@ -15,6 +15,9 @@ pub enum Route {
     /// The view where users will be able to discuss between each others.
     #[at("/r/:room")]
     Room { room: String },
+    /// The private conversation with another user.
+    #[at("/dm/:login")]
+    DirectMessage { login: String },
     /// The place where a client can sign in to the application.
     #[at("/signin")]
     SignIn,
@@ -38,6 +41,7 @@ pub fn switch(route: Route) -> Html {
     match route {
         Route::JoinRoom => html! { <AuthGuard<JoinRoomHOC> /> },
         Route::Room { room } => html! { <AuthGuard<FeedHOC> {room} /> },
+        Route::DirectMessage { login } => html! { <AuthGuard<DirectMessageHOC> {login} /> },
         Route::SignIn => html! { <SignInHOC /> },
         Route::SignUp => html! { <SignUp /> },
         Route::Settings => html! { <AuthGuard<SettingsHOC> /> },
@@ -49,6 +53,9 @@ pub fn switch(route: Route) -> Html {
 impl Route {
     /// Returns whether a route requires the user to be authenticated or not.
     pub fn requires_auth(&self) -> bool {
-        matches!(self, Route::Room { room: _ } | Route::Settings)
+        matches!(
+            self,
+            Route::Room { room: _ } | Route::DirectMessage { login: _ } | Route::Settings
+        )
     }
 }
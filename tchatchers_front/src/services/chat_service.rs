@@ -1,21 +1,42 @@
 // Copyright ⓒ 2022 LABEYE Loïc
 // This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
 
+use std::rc::Rc;
+
+use crate::components::toast::Alert;
 use crate::services::chat_bus::ChatBus;
+use crate::services::toast_bus::ToastBus;
 use futures::{channel::mpsc::Sender, SinkExt, StreamExt};
 use gloo_console::{debug, error};
 use gloo_net::websocket::{futures::WebSocket, Message, WebSocketError};
+use tchatchers_core::codec::from_msgpack;
+use tchatchers_core::protocol::{
+    is_compatible, PROTOCOL_VERSION, SUPPORTED_CAPABILITIES, WS_CLOSE_INCOMPATIBLE_PROTOCOL,
+};
+use tchatchers_core::translation::Translation;
 use tchatchers_core::ws_message::WsMessage;
 use wasm_bindgen_futures::spawn_local;
 use yew_agent::Dispatched;
 
+/// Informs the user that the application has to be reloaded, since it can't
+/// talk to the server anymore.
+fn ask_to_reload(translation: &Translation) {
+    ToastBus::dispatcher().send(Alert {
+        is_success: false,
+        content: translation.get_or_default(
+            "protocol_mismatch",
+            "A new version of the application is available, please reload the page.",
+        ),
+    });
+}
+
 #[derive(Clone, Debug)]
 pub struct WebsocketService {
     pub tx: Sender<String>,
 }
 
 impl WebsocketService {
-    pub fn new(room: &str, bearer: &str) -> Self {
+    pub fn new(room: &str, bearer: &str, translation: Rc<Translation>) -> Self {
         let location = web_sys::window().unwrap().location();
         let host = location.host().unwrap();
         let protocol = location.protocol().unwrap();
@@ -38,6 +59,19 @@ impl WebsocketService {
         let mut event_bus = ChatBus::dispatcher();
 
         spawn_local(async move {
+            // The connection is opened with the handshake, before any other
+            // message is sent.
+            let hello = WsMessage::Hello {
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: SUPPORTED_CAPABILITIES.to_vec(),
+            };
+            if write
+                .send(Message::Text(serde_json::to_string(&hello).unwrap()))
+                .await
+                .is_err()
+            {
+                return;
+            }
             while let Some(s) = in_rx.next().await {
                 write.send(Message::Text(s)).await.unwrap();
             }
@@ -46,17 +80,25 @@ impl WebsocketService {
         spawn_local(async move {
             while let Some(msg) = read.next().await {
                 match msg {
-                    Ok(Message::Text(data)) => {
-                        if let Ok(msg) = serde_json::from_str(&data) {
-                            event_bus.send(msg);
-                        }
-                    }
-                    Ok(Message::Bytes(b)) => {
-                        let decoded = std::str::from_utf8(&b);
-                        if let Ok(val) = decoded {
-                            if let Ok(msg) = serde_json::from_str(val) {
-                                event_bus.send(msg);
+                    Ok(frame) => {
+                        let decoded = match frame {
+                            Message::Text(data) => serde_json::from_str(&data).ok(),
+                            // The server sends binary frames once MessagePack
+                            // has been negotiated.
+                            Message::Bytes(b) => from_msgpack(&b)
+                                .ok()
+                                .or_else(|| serde_json::from_slice(&b).ok()),
+                        };
+                        if let Some(msg) = decoded {
+                            if let WsMessage::Hello {
+                                protocol_version, ..
+                            } = msg
+                            {
+                                if !is_compatible(protocol_version) {
+                                    ask_to_reload(&translation);
+                                }
                             }
+                            event_bus.send(msg);
                         }
                     }
                     Err(e) => match e {
@@ -67,6 +109,9 @@ impl WebsocketService {
                         WebSocketError::ConnectionClose(e) => {
                             error!("The connection has been closed :", e.code);
                             error!("Error :", e.reason);
+                            if e.code == WS_CLOSE_INCOMPATIBLE_PROTOCOL {
+                                ask_to_reload(&translation);
+                            }
                             event_bus.send(WsMessage::ConnectionClosed);
                         }
                         WebSocketError::MessageSendError(e) => {
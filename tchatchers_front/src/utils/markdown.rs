@@ -0,0 +1,73 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! Renders the formatted content of the messages.
+//!
+//! Only the tree returned by the shared parser is rendered, the content is
+//! never injected as raw HTML.
+
+use tchatchers_core::markdown::{parse, Block, Inline};
+use tchatchers_core::mention::{split_mentions, TextSegment};
+use yew::{html, Html};
+
+/// Renders the formatted content of a message.
+///
+/// # Arguments
+///
+/// - content : The content of the message.
+/// - login : The login of the current user, whose mentions are highlighted.
+pub fn render_markdown(content: &str, login: &str) -> Html {
+    parse(content)
+        .iter()
+        .map(|block| render_block(block, login))
+        .collect()
+}
+
+/// Renders a block of a formatted message.
+fn render_block(block: &Block, login: &str) -> Html {
+    match block {
+        Block::Paragraph(inlines) => html! {
+            <span class="block">{render_inlines(inlines, login)}</span>
+        },
+        Block::Code { language, code } => html! {
+            <code class="block whitespace-pre-wrap font-mono text-xs bg-black/20 rounded p-1 my-1" data-language={language.clone()}>{code}</code>
+        },
+    }
+}
+
+/// Renders spans of formatted text.
+fn render_inlines(inlines: &[Inline], login: &str) -> Html {
+    inlines
+        .iter()
+        .map(|inline| render_inline(inline, login))
+        .collect()
+}
+
+/// Renders a span of formatted text.
+fn render_inline(inline: &Inline, login: &str) -> Html {
+    match inline {
+        Inline::Text(text) => split_mentions(text)
+            .into_iter()
+            .map(|segment| match segment {
+                TextSegment::Text(text) => html! { {text} },
+                TextSegment::Mention(mention) => {
+                    let class = match mention == login {
+                        true => "font-semibold rounded px-1 bg-yellow-200 text-gray-800",
+                        false => "font-semibold",
+                    };
+                    html! { <span {class}>{"@"}{mention}</span> }
+                }
+            })
+            .collect(),
+        Inline::LineBreak => html! { <br/> },
+        Inline::Code(code) => html! {
+            <code class="font-mono text-xs bg-black/20 rounded px-1">{code}</code>
+        },
+        Inline::Bold(children) => html! { <strong>{render_inlines(children, login)}</strong> },
+        Inline::Italic(children) => html! { <em>{render_inlines(children, login)}</em> },
+        Inline::Strikethrough(children) => html! { <del>{render_inlines(children, login)}</del> },
+        Inline::Link { url, label } => html! {
+            <a class="underline" href={url.clone()} target="_blank" rel="noopener noreferrer">{render_inlines(label, login)}</a>
+        },
+    }
+}
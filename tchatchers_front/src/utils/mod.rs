@@ -2,4 +2,6 @@
 // This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
 
 pub mod client_context;
+pub mod markdown;
+pub mod outbox;
 pub mod requester;
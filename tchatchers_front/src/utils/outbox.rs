@@ -0,0 +1,54 @@
+// Copyright ⓒ 2022 LABEYE Loïc
+// This tool is distributed under the MIT License, check out [here](https://github.com/nag763/tchatchers/blob/main/LICENSE.MD).
+
+//! The messages written by the user that the server hasn't acknowledged yet
+//! are kept in the local storage, so that they aren't lost when the page is
+//! closed while the user is disconnected.
+
+use tchatchers_core::ws_message::WsMessageContent;
+use web_sys::Storage;
+
+/// The prefix of the keys under which the messages of a room are stored.
+const OUTBOX_KEY_PREFIX: &str = "outbox:";
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+fn key(room: &str) -> String {
+    format!("{OUTBOX_KEY_PREFIX}{room}")
+}
+
+/// Returns the messages stored for a room.
+///
+/// # Arguments
+///
+/// - room : The room name.
+pub fn load(room: &str) -> Vec<WsMessageContent> {
+    let Some(storage) = local_storage() else {
+        return vec![];
+    };
+    match storage.get_item(&key(room)) {
+        Ok(Some(value)) => serde_json::from_str(&value).unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Replaces the messages stored for a room.
+///
+/// # Arguments
+///
+/// - room : The room name.
+/// - messages : The messages not acknowledged yet, the oldest first.
+pub fn store(room: &str, messages: &[WsMessageContent]) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+    let result = match messages.is_empty() {
+        true => storage.remove_item(&key(room)),
+        false => storage.set_item(&key(room), &serde_json::to_string(messages).unwrap()),
+    };
+    if result.is_err() {
+        gloo_console::error!("The messages waiting to be sent couldn't be stored");
+    }
+}